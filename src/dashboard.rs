@@ -0,0 +1,430 @@
+//! The `tt -a` dashboard: a live table of active timers driven by single-key actions.
+//!
+//! The screen is built as a list of lines each frame and only lines that changed since the last
+//! frame are rewritten, so the countdowns tick without the whole screen flickering.
+
+use crate::{color, format_countdown, ActiveTimer, FinishedTimer, TimerState};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, queue, style::Print, terminal};
+use rusqlite::{Connection, Result};
+use std::io::{stdout, Write};
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// How much `+` and `-` change a timer by.
+const STEP: Duration = Duration::from_secs(60);
+
+/// Finished timers stay listed under "Recently finished" for this long.
+const RECENT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Key help, on two lines so it fits an 80-column terminal. Kill is on `K` because `k`
+/// moves the selection up.
+const HELP: [&str; 2] = ["↑/↓ j/k select · p pause · +/- 1 min · r restart · e edit", "K kill · X kill all · q quit"];
+const RINGING_HELP: [&str; 2] = ["↑/↓ j/k select · z snooze · s stop · r restart", "K kill · q quit"];
+
+enum Mode {
+    Normal,
+    /// Editing the selected timer's message; holds the text typed so far.
+    Edit(String),
+    ConfirmKillAll,
+}
+
+struct Dashboard {
+    conn: Connection,
+    timers: Vec<ActiveTimer>,
+    /// Timers that finished within RECENT_WINDOW.
+    recent: Vec<FinishedTimer>,
+    /// Id of the selected timer, so the selection stays put when timers come and go.
+    selected: Option<i64>,
+    mode: Mode,
+    status: Option<(String, Instant)>,
+    /// Lines drawn in the last frame, for diffing.
+    screen: Vec<String>,
+    size: (u16, u16),
+}
+
+/// Shows the dashboard until the user quits.
+pub fn run() -> Result<()> {
+    // RAII guard to ensure terminal is always restored
+    struct TerminalGuard;
+    impl Drop for TerminalGuard {
+        fn drop(&mut self) {
+            let _ = terminal::disable_raw_mode();
+            let _ = crossterm::execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        }
+    }
+
+    let mut dashboard = Dashboard {
+        conn: crate::init_db()?,
+        timers: Vec::new(),
+        recent: Vec::new(),
+        selected: None,
+        mode: Mode::Normal,
+        status: None,
+        screen: Vec::new(),
+        size: (0, 0),
+    };
+
+    crossterm::execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide).ok();
+    terminal::enable_raw_mode().ok();
+    // Guard ensures cleanup happens even on panic or early return
+    let _guard = TerminalGuard;
+
+    // Redraw right after any input, as soon as another process changes the database (a timer
+    // started in another terminal, a snooze, the tray...) and otherwise once a second for the
+    // countdowns.
+    let mut last_version = None;
+    let mut last_draw: Option<Instant> = None;
+    let mut dirty = true;
+    loop {
+        let version = crate::data_version(&dashboard.conn);
+        if dirty || version != last_version || last_draw.is_none_or(|t| t.elapsed() >= Duration::from_secs(1)) {
+            dashboard.reload()?;
+            dashboard.draw();
+            last_version = version;
+            last_draw = Some(Instant::now());
+            dirty = false;
+        }
+
+        if event::poll(Duration::from_millis(100)).unwrap_or(false) {
+            match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    if !dashboard.handle_key(key)? {
+                        return Ok(());
+                    }
+                    dirty = true;
+                }
+                Ok(Event::Resize(..)) => dirty = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Dashboard {
+    /// Reloads timers from the database. Timers whose process is gone (e.g. killed from outside
+    /// tt) are cleaned up; ringing ones stay until they are answered.
+    fn reload(&mut self) -> Result<()> {
+        let mut timers = crate::load_active_timers_db(&self.conn)?;
+        timers.retain(|timer| {
+            let alive = crate::process_alive(timer.pid);
+            if !alive {
+                let _ = crate::unregister_active_timer_db(&self.conn, timer.id);
+            }
+            alive
+        });
+        self.timers = timers;
+        let since = chrono::Local::now() - chrono::Duration::from_std(RECENT_WINDOW).unwrap_or_default();
+        self.recent = crate::recent_finished_db(&self.conn, since, 5)?;
+        if !self.timers.iter().any(|t| Some(t.id) == self.selected) {
+            self.selected = self.timers.first().map(|t| t.id);
+        }
+        Ok(())
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.timers.iter().position(|t| Some(t.id) == self.selected)
+    }
+
+    fn selected_timer(&self) -> Option<&ActiveTimer> {
+        self.selected_index().map(|i| &self.timers[i])
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        if let Some(index) = self.selected_index() {
+            let index = index.saturating_add_signed(offset).min(self.timers.len() - 1);
+            self.selected = Some(self.timers[index].id);
+        }
+    }
+
+    fn set_status(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), Instant::now()));
+    }
+
+    /// Handles one key press. Returns false when the dashboard should close.
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(false);
+        }
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Edit(mut text) => {
+                match key.code {
+                    KeyCode::Enter => {
+                        if let Some(id) = self.selected {
+                            crate::set_active_timer_message_db(&self.conn, id, text.trim())?;
+                            self.set_status(format!("Updated message of timer #{}", id));
+                        }
+                    }
+                    KeyCode::Esc => {}
+                    KeyCode::Backspace => {
+                        text.pop();
+                        self.mode = Mode::Edit(text);
+                    }
+                    KeyCode::Char(c) => {
+                        text.push(c);
+                        self.mode = Mode::Edit(text);
+                    }
+                    _ => self.mode = Mode::Edit(text),
+                }
+                return Ok(true);
+            }
+            Mode::ConfirmKillAll => {
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    let count = self.timers.len();
+                    for timer in &self.timers {
+                        crate::kill_active_timer_db(&self.conn, timer.id)?;
+                    }
+                    self.set_status(format!("Killed {} timer{}", count, if count == 1 { "" } else { "s" }));
+                }
+                return Ok(true);
+            }
+            Mode::Normal => {}
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = self.timers.first().map(|t| t.id),
+            KeyCode::End | KeyCode::Char('G') => self.selected = self.timers.last().map(|t| t.id),
+            KeyCode::Char('X') if !self.timers.is_empty() => self.mode = Mode::ConfirmKillAll,
+            _ => {
+                if let Some(timer) = self.selected_timer().cloned() {
+                    if timer.ringing() {
+                        self.ringing_action(key.code, timer.id)?;
+                    } else {
+                        self.timer_action(key.code, timer)?;
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Keys that answer a ringing timer, like the popup buttons do.
+    fn ringing_action(&mut self, code: KeyCode, id: i64) -> Result<()> {
+        let (action, done) = match code {
+            KeyCode::Char('z') => {
+                let duration = crate::get_snooze_duration_and_str().1;
+                let done = format!("Snoozed timer #{} for {}", id, duration);
+                (crate::TimerAction::Snooze { duration }, done)
+            }
+            KeyCode::Char('s') => (crate::TimerAction::Stop, format!("Stopped timer #{}", id)),
+            KeyCode::Char('r') => (crate::TimerAction::Restart, format!("Restarted timer #{}", id)),
+            KeyCode::Char('K') | KeyCode::Delete => {
+                crate::kill_active_timer_db(&self.conn, id)?;
+                self.set_status(format!("Killed timer #{}", id));
+                return Ok(());
+            }
+            KeyCode::Char('p') | KeyCode::Char('+') | KeyCode::Char('-') | KeyCode::Char('e') => {
+                self.set_status("Timer is ringing: z to snooze, s to stop, r to restart");
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        if crate::request_timer_action_db(&self.conn, id, &action)? {
+            self.set_status(done);
+        } else {
+            self.set_status(format!("Timer #{} is not ringing yet", id));
+        }
+        Ok(())
+    }
+
+    /// Keys that act on the selected timer.
+    fn timer_action(&mut self, code: KeyCode, timer: ActiveTimer) -> Result<()> {
+        let ActiveTimer { id, paused, total, remaining, message, .. } = timer;
+        match code {
+            KeyCode::Char('K') | KeyCode::Delete => {
+                crate::kill_active_timer_db(&self.conn, id)?;
+                self.set_status(format!("Killed timer #{}", id));
+            }
+            KeyCode::Char('p') | KeyCode::Char(' ') => {
+                if paused {
+                    crate::resume_active_timer_db(&self.conn, id)?;
+                    self.set_status(format!("Resumed timer #{}", id));
+                } else {
+                    crate::pause_active_timer_db(&self.conn, id)?;
+                    self.set_status(format!("Paused timer #{}", id));
+                }
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                crate::set_active_timer_duration_db(&self.conn, id, total + STEP)?;
+                self.set_status(format!("Added a minute to timer #{}", id));
+            }
+            KeyCode::Char('-') | KeyCode::Char('_') => {
+                if remaining > STEP {
+                    crate::set_active_timer_duration_db(&self.conn, id, total - STEP)?;
+                    self.set_status(format!("Took a minute off timer #{}", id));
+                } else {
+                    self.set_status("Less than a minute left");
+                }
+            }
+            KeyCode::Char('r') => {
+                crate::restart_active_timer_db(&self.conn, id)?;
+                self.set_status(format!("Restarted timer #{}", id));
+            }
+            KeyCode::Char('e') | KeyCode::Enter => self.mode = Mode::Edit(message),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Builds the frame as plain lines with color codes.
+    fn render(&mut self, width: usize) -> Vec<String> {
+        let id_width = 3;
+        let dur_width = 7;
+        let time_width = 8;
+        let bar_width = if width >= 90 { 20 } else { 10 };
+        // Message takes what's left, capped for readability
+        let message_width = width.saturating_sub(2 + id_width + dur_width + bar_width + time_width + 2 * 4 + 9).clamp(10, 50);
+
+        let mut lines = Vec::new();
+        lines.push(format!(
+            "  {}  {}  {}  {}  {}",
+            color(&fit("ID", id_width, true), "gray"),
+            color(&fit("Dur", dur_width, false), "gray"),
+            color(&fit("Message", message_width, false), "gray"),
+            color(&fit("Progress", bar_width, false), "gray"),
+            color("Left", "gray"),
+        ));
+        lines.push("-".repeat(2 + id_width + dur_width + message_width + bar_width + time_width + 2 * 4));
+
+        for timer in &self.timers {
+            let selected = Some(timer.id) == self.selected;
+            let marker = if selected { color("❯", "cyan") } else { " ".to_string() };
+            let fraction = timer.percent() as f64 / 100.0;
+            let (bar_color, left) = match timer.state() {
+                TimerState::Ringing => ("red", color(&format!("🔔 ringing +{}", format_countdown(timer.overtime)), "red")),
+                TimerState::Paused => ("yellow", color(&format!("{} ⏸ paused", format_countdown(timer.remaining)), "yellow")),
+                TimerState::Running => ("green", color(&format_countdown(timer.remaining), "green")),
+            };
+            let label = crate::labeled_message(&timer.message, &timer.tags, timer.project.as_deref());
+            let message = if selected { color(&fit(&label, message_width, false), "white") } else { color(&fit(&label, message_width, false), "purple") };
+            lines.push(format!(
+                "{} {}  {}  {}  {}  {}",
+                marker,
+                color(&fit(&timer.id.to_string(), id_width, true), "red"),
+                color(&fit(&timer.duration, dur_width, false), "pink"),
+                message,
+                color(&progress_bar(fraction, bar_width), bar_color),
+                left,
+            ));
+        }
+        if self.timers.is_empty() {
+            lines.push(color("No active timers", "gray"));
+        }
+        lines.push(String::new());
+
+        if !self.recent.is_empty() {
+            lines.push(color("Recently finished", "gray"));
+            for FinishedTimer { finished, duration, message, outcome } in &self.recent {
+                let ago = (chrono::Local::now() - *finished).num_minutes();
+                let ago = if ago < 1 { "just now".to_string() } else { format!("{}m ago", ago) };
+                lines.push(format!(
+                    "  {}  {}  {}  {}  {}",
+                    " ".repeat(id_width),
+                    color(&fit(duration, dur_width, false), "gray"),
+                    color(&fit(message, message_width, false), "gray"),
+                    color(&fit(outcome, bar_width, false), "gray"),
+                    color(&ago, "gray"),
+                ));
+            }
+            lines.push(String::new());
+        }
+
+        // Show status message if recent
+        match &self.status {
+            Some((message, instant)) if instant.elapsed() < Duration::from_secs(2) => lines.push(color(message, "green")),
+            _ => {
+                self.status = None;
+                lines.push(String::new());
+            }
+        }
+
+        // Prompts take the first help line and leave the second blank, so the screen keeps its height.
+        lines.extend(match &self.mode {
+            Mode::Normal if self.selected_timer().is_some_and(ActiveTimer::ringing) => RINGING_HELP.map(|line| color(line, "gray")),
+            Mode::Normal => HELP.map(|line| color(line, "gray")),
+            Mode::ConfirmKillAll => [color(&format!("Kill all {} timers? [y/N]", self.timers.len()), "yellow"), String::new()],
+            Mode::Edit(text) => [format!("{} {}", color("Message (Enter to save, Esc to cancel):", "gray"), text), String::new()],
+        });
+        lines
+    }
+
+    /// Redraws only the lines that changed since the last frame.
+    fn draw(&mut self) {
+        let mut out = stdout();
+        let size = terminal::size().ok().filter(|&(w, h)| w > 0 && h > 0).unwrap_or((80, 24));
+        if size != self.size {
+            let _ = queue!(out, terminal::Clear(terminal::ClearType::All));
+            self.screen.clear();
+            self.size = size;
+        }
+        let mut lines = self.render(size.0 as usize);
+        lines.truncate(size.1 as usize);
+
+        for (row, line) in lines.iter().enumerate() {
+            if self.screen.get(row) != Some(line) {
+                let _ = queue!(out, cursor::MoveTo(0, row as u16), Print(line), terminal::Clear(terminal::ClearType::UntilNewLine));
+            }
+        }
+        if lines.len() < self.screen.len() {
+            let _ = queue!(out, cursor::MoveTo(0, lines.len() as u16), terminal::Clear(terminal::ClearType::FromCursorDown));
+        }
+        // Only show the cursor while typing a message.
+        if let (Mode::Edit(_), Some(last)) = (&self.mode, lines.last()) {
+            let column = UnicodeWidthStr::width(strip_ansi(last).as_str());
+            let _ = queue!(out, cursor::MoveTo(column as u16, lines.len() as u16 - 1), cursor::Show);
+        } else {
+            let _ = queue!(out, cursor::Hide);
+        }
+        let _ = out.flush();
+        self.screen = lines;
+    }
+}
+
+/// Pads or truncates (with "…") to exactly `width` columns.
+fn fit(s: &str, width: usize, right_align: bool) -> String {
+    let mut text = String::new();
+    let mut used = 0;
+    if UnicodeWidthStr::width(s) > width {
+        for c in s.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w + 1 > width {
+                break;
+            }
+            text.push(c);
+            used += w;
+        }
+        text.push('…');
+        used += 1;
+    } else {
+        text.push_str(s);
+        used = UnicodeWidthStr::width(s);
+    }
+    let padding = " ".repeat(width.saturating_sub(used));
+    if right_align { padding + &text } else { text + &padding }
+}
+
+fn progress_bar(fraction: f64, width: usize) -> String {
+    let filled = ((fraction.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Removes color codes, to measure how wide a line is on screen.
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1B' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -0,0 +1,225 @@
+//! `tt history export`: writes timer_history as CSV, JSON, NDJSON or an iCalendar file.
+//!
+//! Every format carries the same fields (see `Record`), so an export can be read back by
+//! `tt history import` or loaded into a spreadsheet, calendar or time tracker.
+
+use crate::history::{self, HistoryEntry, HistoryFilter, Paging};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::io::Write;
+
+/// Column order for CSV.
+pub const CSV_HEADER: [&str; 13] = [
+    "id",
+    "start",
+    "planned_end",
+    "end",
+    "duration",
+    "duration_seconds",
+    "elapsed_seconds",
+    "message",
+    "tags",
+    "project",
+    "foreground",
+    "outcome",
+    "note",
+];
+
+/// One exported history entry. Times are local, "YYYY-MM-DD HH:MM:SS" in CSV and RFC 3339
+/// with the offset in JSON.
+#[derive(Serialize)]
+pub struct Record {
+    pub id: i64,
+    pub start: String,
+    /// When the timer was set to go off.
+    pub planned_end: String,
+    /// When the alarm was answered, if it was.
+    pub end: Option<String>,
+    pub duration: String,
+    pub duration_seconds: u64,
+    /// Time from start to end, including time spent ringing.
+    pub elapsed_seconds: Option<u64>,
+    pub message: String,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub foreground: bool,
+    pub outcome: Option<String>,
+    pub note: Option<String>,
+}
+
+struct Times {
+    start: DateTime<Local>,
+    planned_end: DateTime<Local>,
+    end: Option<DateTime<Local>>,
+}
+
+/// Start, planned end and end of an entry, when they can be read.
+fn times(entry: &HistoryEntry) -> Option<Times> {
+    let start = crate::parse_db_time(&entry.timestamp)?;
+    let duration = humantime::parse_duration(&entry.duration).unwrap_or_default();
+    let planned_end = start + chrono::Duration::from_std(duration).unwrap_or_default();
+    let end = entry.finished.as_deref().and_then(crate::parse_db_time);
+    Some(Times { start, planned_end, end })
+}
+
+fn record(entry: &HistoryEntry, time: impl Fn(&DateTime<Local>) -> String) -> Option<Record> {
+    let Times { start, planned_end, end } = times(entry)?;
+    Some(Record {
+        id: entry.id,
+        start: time(&start),
+        planned_end: time(&planned_end),
+        end: end.as_ref().map(&time),
+        duration: entry.duration.clone(),
+        duration_seconds: humantime::parse_duration(&entry.duration).map(|d| d.as_secs()).unwrap_or_default(),
+        elapsed_seconds: end.and_then(|end| (end - start).to_std().ok()).map(|d| d.as_secs()),
+        message: entry.message.clone(),
+        tags: entry.tags.clone(),
+        project: entry.project.clone(),
+        foreground: entry.fg,
+        outcome: entry.outcome.clone(),
+        note: entry.note.clone(),
+    })
+}
+
+fn csv(entries: &[HistoryEntry], out: &mut impl Write) -> std::io::Result<()> {
+    use crate::csv_field;
+
+    writeln!(out, "{}", CSV_HEADER.join(","))?;
+    for entry in entries {
+        let Some(r) = record(entry, |t| t.format("%Y-%m-%d %H:%M:%S").to_string()) else { continue };
+        let fields = [
+            r.id.to_string(),
+            r.start,
+            r.planned_end,
+            r.end.unwrap_or_default(),
+            r.duration,
+            r.duration_seconds.to_string(),
+            r.elapsed_seconds.map(|s| s.to_string()).unwrap_or_default(),
+            r.message,
+            r.tags.join(";"),
+            r.project.unwrap_or_default(),
+            r.foreground.to_string(),
+            r.outcome.unwrap_or_default(),
+            r.note.unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+fn json_records(entries: &[HistoryEntry]) -> Vec<Record> {
+    entries.iter().filter_map(|e| record(e, |t| t.to_rfc3339())).collect()
+}
+
+/// Escapes TEXT values for iCalendar (RFC 5545 section 3.3.11).
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Writes a content line, folded at 75 bytes as iCalendar requires.
+fn ics_line(out: &mut impl Write, line: &str) -> std::io::Result<()> {
+    let mut rest = line;
+    let mut first = true;
+    while !rest.is_empty() {
+        let limit = if first { 75 } else { 74 };
+        let mut cut = rest.len().min(limit);
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        write!(out, "{}{}\r\n", if first { "" } else { " " }, &rest[..cut])?;
+        rest = &rest[cut..];
+        first = false;
+    }
+    Ok(())
+}
+
+fn ics(entries: &[HistoryEntry], out: &mut impl Write) -> std::io::Result<()> {
+    let utc = |t: &DateTime<Local>| t.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string();
+    let stamp = utc(&Local::now());
+    ics_line(out, "BEGIN:VCALENDAR")?;
+    ics_line(out, "VERSION:2.0")?;
+    ics_line(out, &format!("PRODID:-//{}//{}//EN", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))?;
+    for entry in entries {
+        let Some(Times { start, planned_end, end }) = times(entry) else { continue };
+        let summary = if entry.message.is_empty() { format!("Timer {}", entry.duration) } else { entry.message.clone() };
+        let mut description = format!("Duration: {}", entry.duration);
+        if let Some(project) = &entry.project {
+            description.push_str(&format!("\nProject: {}", project));
+        }
+        if let Some(outcome) = &entry.outcome {
+            description.push_str(&format!("\nOutcome: {}", outcome));
+        }
+        if let Some(note) = entry.note.as_deref().filter(|n| !n.is_empty()) {
+            description.push_str(&format!("\nNote: {}", note));
+        }
+        ics_line(out, "BEGIN:VEVENT")?;
+        ics_line(out, &format!("UID:tt-{}-{}@{}", entry.id, utc(&start), env!("CARGO_PKG_NAME")))?;
+        ics_line(out, &format!("DTSTAMP:{}", stamp))?;
+        ics_line(out, &format!("DTSTART:{}", utc(&start)))?;
+        ics_line(out, &format!("DTEND:{}", utc(&end.unwrap_or(planned_end).max(start))))?;
+        ics_line(out, &format!("SUMMARY:{}", ics_text(&summary)))?;
+        ics_line(out, &format!("DESCRIPTION:{}", ics_text(&description)))?;
+        if !entry.tags.is_empty() {
+            let tags: Vec<String> = entry.tags.iter().map(|t| ics_text(t)).collect();
+            ics_line(out, &format!("CATEGORIES:{}", tags.join(",")))?;
+        }
+        ics_line(out, "END:VEVENT")?;
+    }
+    ics_line(out, "END:VCALENDAR")
+}
+
+/// Writes the entries matching `filter` in `format` to `output`, or stdout when it is None.
+pub fn run(filter: &HistoryFilter, paging: &Paging, format: &str, output: Option<&std::path::Path>) {
+    if !["csv", "json", "ndjson", "ics"].contains(&format) {
+        eprintln!("Unknown format '{}'. Use csv, json, ndjson or ics.", format);
+        std::process::exit(2);
+    }
+    let entries = match crate::init_db().and_then(|conn| history::query(&conn, filter, paging)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Could not read the history: {}", e);
+            std::process::exit(1);
+        }
+    };
+    // An entry whose start time can't be read has no place in any format; name it rather than
+    // leave it out silently.
+    let (entries, unreadable): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| crate::parse_db_time(&e.timestamp).is_some());
+    if !unreadable.is_empty() {
+        let skipped: Vec<String> = unreadable.iter().map(|e| format!("{} ({})", e.id, e.timestamp)).collect();
+        eprintln!("Skipped timers with an unreadable start time: {}", skipped.join(", "));
+    }
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => match std::fs::File::create(path) {
+            Ok(file) => Box::new(std::io::BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("Could not create {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let written = match format {
+        "csv" => csv(&entries, &mut out),
+        "json" => serde_json::to_writer_pretty(&mut out, &json_records(&entries))
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(out)),
+        "ndjson" => json_records(&entries).iter().try_for_each(|r| {
+            serde_json::to_writer(&mut out, r).map_err(std::io::Error::from)?;
+            writeln!(out)
+        }),
+        _ => ics(&entries, &mut out),
+    };
+    if let Err(e) = written.and_then(|_| out.flush()) {
+        eprintln!("Could not write the export: {}", e);
+        std::process::exit(1);
+    }
+    if let Some(path) = output {
+        eprintln!("Exported {} timers to {}", entries.len(), path.display());
+    }
+}
@@ -0,0 +1,399 @@
+//! `tt history`: filtered queries over timer_history.
+//!
+//! The filters are turned into a single SQL query, so they combine freely and paging happens
+//! in the database. Dates for `--since`/`--until` accept the usual natural forms; see
+//! `parse_when`.
+
+use chrono::{Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::{params_from_iter, types::Value, Connection, Result};
+
+/// Filters shared by `tt history` and its subcommands.
+#[derive(clap::Args, Default, Clone)]
+pub struct HistoryFilter {
+    /// Only timers started at or after this time: "today", "yesterday", "monday", "last week",
+    /// "3d" or "2 hours ago", "2024-05-01" or "2024-05-01 14:00"
+    #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+    pub since: Option<NaiveDateTime>,
+
+    /// Only timers started before this time (same forms as --since; a day includes the whole day)
+    #[arg(long, value_name = "WHEN", value_parser = parse_until)]
+    pub until: Option<NaiveDateTime>,
+
+    /// Only timers whose message or note contains TEXT (case-insensitive)
+    #[arg(short, long, value_name = "TEXT")]
+    pub grep: Option<String>,
+
+    /// Only timers with this tag (repeat to require several)
+    #[arg(short, long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Only timers in this project (case-insensitive)
+    #[arg(long, value_name = "NAME")]
+    pub project: Option<String>,
+
+    /// Only foreground timers
+    #[arg(long, conflicts_with = "bg")]
+    pub fg: bool,
+
+    /// Only background timers
+    #[arg(long)]
+    pub bg: bool,
+
+    /// Only timers that ended this way: done, stopped, snoozed, restarted, auto-stopped,
+    /// auto-snoozed, or "none" for ones that never finished
+    #[arg(long, value_name = "OUTCOME")]
+    pub outcome: Option<String>,
+}
+
+/// Paging and order for commands that list entries.
+#[derive(clap::Args, Default, Clone)]
+pub struct Paging {
+    /// How many entries to show per page (default 20 when listing, all when exporting)
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+
+    /// Which page to show, starting at 1
+    #[arg(short, long, default_value_t = 1)]
+    pub page: usize,
+
+    /// Oldest first instead of newest first
+    #[arg(short, long)]
+    pub reverse: bool,
+}
+
+/// A timer_history row.
+pub struct HistoryEntry {
+    pub id: i64,
+    pub timestamp: String,
+    pub duration: String,
+    pub message: String,
+    pub fg: bool,
+    pub outcome: Option<String>,
+    pub note: Option<String>,
+    pub finished: Option<String>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+}
+
+/// Runs the filter against timer_history. Without a limit every match is returned.
+pub fn query(conn: &Connection, filter: &HistoryFilter, paging: &Paging) -> Result<Vec<HistoryEntry>> {
+    select(conn, filter, paging, 0)
+}
+
+/// Like `query`, but also says whether there are more matches after this page.
+pub fn query_page(conn: &Connection, filter: &HistoryFilter, paging: &Paging) -> Result<(Vec<HistoryEntry>, bool)> {
+    // Ask for one row past the page to find out if another page exists.
+    let mut entries = select(conn, filter, paging, 1)?;
+    let more = paging.limit.is_some_and(|limit| limit > 0 && entries.len() > limit);
+    if let Some(limit) = paging.limit {
+        entries.truncate(limit);
+    }
+    Ok((entries, more))
+}
+
+/// Runs the query for a page, fetching `extra` rows past its end.
+fn select(conn: &Connection, filter: &HistoryFilter, paging: &Paging, extra: usize) -> Result<Vec<HistoryEntry>> {
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
+    let mut bind = |condition: &str, value: Value| {
+        values.push(value);
+        conditions.push(condition.replace('?', &format!("?{}", values.len())));
+    };
+
+    let format = |t: &NaiveDateTime| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    if let Some(since) = &filter.since {
+        bind("timestamp >= ?", Value::Text(format(since)));
+    }
+    if let Some(until) = &filter.until {
+        bind("timestamp < ?", Value::Text(format(until)));
+    }
+    if let Some(text) = &filter.grep {
+        bind(
+            "(instr(lower(message), lower(?)) > 0 OR instr(lower(coalesce(note, '')), lower(?)) > 0)",
+            Value::Text(text.clone()),
+        );
+    }
+    for tag in &filter.tags {
+        bind(
+            "EXISTS (SELECT 1 FROM timer_tags JOIN tags ON tags.id = tag_id WHERE history_id = timer_history.id AND name = ?)",
+            Value::Text(crate::normalize_tag(tag)),
+        );
+    }
+    if let Some(project) = &filter.project {
+        bind("lower(project) = lower(?)", Value::Text(project.trim().to_string()));
+    }
+    if filter.fg || filter.bg {
+        bind("fg = ?", Value::Integer(filter.fg as i64));
+    }
+    match filter.outcome.as_deref() {
+        Some("none") => {}
+        Some(outcome) => bind("outcome = ?", Value::Text(outcome.to_lowercase())),
+        None => {}
+    }
+    if filter.outcome.as_deref() == Some("none") {
+        conditions.push("outcome IS NULL".to_string());
+    }
+
+    let mut sql = "SELECT id, timestamp, duration, message, fg, outcome, note, finished, project,
+                (SELECT group_concat(name, ' ') FROM timer_tags JOIN tags ON tags.id = tag_id WHERE history_id = timer_history.id)
+         FROM timer_history"
+        .to_string();
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql.push_str(if paging.reverse { " ORDER BY timestamp ASC, id ASC" } else { " ORDER BY timestamp DESC, id DESC" });
+    if let Some(limit) = paging.limit {
+        let offset = paging.page.saturating_sub(1) * limit;
+        sql.push_str(&format!(" LIMIT {} OFFSET {}", limit + extra, offset));
+    }
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        Ok(HistoryEntry {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            duration: row.get(2)?,
+            message: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            fg: row.get(4)?,
+            outcome: row.get(5)?,
+            note: row.get(6)?,
+            finished: row.get(7)?,
+            project: row.get(8)?,
+            tags: row
+                .get::<_, Option<String>>(9)?
+                .map(|tags| tags.split(' ').map(str::to_string).collect())
+                .unwrap_or_default(),
+        })
+    })?;
+    rows.collect()
+}
+
+/// Deletes the timer_history rows matching `condition` along with their tag links, then
+/// drops tags nothing uses any more. Returns how many were deleted.
+fn remove(conn: &Connection, condition: &str, values: Vec<Value>) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        &format!("DELETE FROM timer_tags WHERE history_id IN (SELECT id FROM timer_history WHERE {})", condition),
+        params_from_iter(values.iter()),
+    )?;
+    let deleted = tx.execute(&format!("DELETE FROM timer_history WHERE {}", condition), params_from_iter(values.iter()))?;
+    tx.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM timer_tags UNION SELECT tag_id FROM active_timer_tags)",
+        [],
+    )?;
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// Shrinks the database file after rows were deleted; SQLite keeps the freed pages otherwise.
+/// This rewrites the whole file, so only the explicit delete/prune/clear commands do it, and
+/// a failure (e.g. another tt holding the database) doesn't undo the delete.
+fn compact(conn: &Connection, deleted: usize) -> usize {
+    if deleted > 0 {
+        if let Err(e) = conn.execute_batch("VACUUM") {
+            eprintln!("Could not compact the timer database: {}", e);
+        }
+    }
+    deleted
+}
+
+/// Deletes the entries started before `before`, except those of timers still counting down
+/// (the entry and, for a snooze or restart, the one that started the timer), whose outcome is
+/// yet to be written and which `tt again` may still need.
+fn remove_before(conn: &Connection, before: NaiveDateTime) -> Result<usize> {
+    remove(
+        conn,
+        "timestamp < ?1 AND id NOT IN (
+            SELECT history_id FROM active_timers WHERE history_id IS NOT NULL
+            UNION SELECT run_id FROM timer_history WHERE run_id IS NOT NULL AND id IN (SELECT history_id FROM active_timers))",
+        vec![Value::Text(before.format("%Y-%m-%d %H:%M:%S").to_string())],
+    )
+}
+
+/// Deletes the given entries. Returns how many existed.
+pub fn delete(conn: &Connection, ids: &[i64]) -> Result<usize> {
+    let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("?{}", i)).collect();
+    let deleted = remove(conn, &format!("id IN ({})", placeholders.join(", ")), ids.iter().map(|&id| Value::Integer(id)).collect())?;
+    Ok(compact(conn, deleted))
+}
+
+/// Deletes the entries started before `before`.
+pub fn prune(conn: &Connection, before: NaiveDateTime) -> Result<usize> {
+    Ok(compact(conn, remove_before(conn, before)?))
+}
+
+/// Deletes the whole history.
+pub fn clear(conn: &Connection) -> Result<usize> {
+    Ok(compact(conn, remove(conn, "1", Vec::new())?))
+}
+
+/// Prunes entries older than TT_HISTORY_RETENTION (e.g. "90d"), if it is set. This runs on
+/// every timer start, so it leaves compacting the file to `tt history prune`.
+pub fn apply_retention(conn: &Connection) -> Result<usize> {
+    match crate::get_env_duration("TT_HISTORY_RETENTION").and_then(|age| ChronoDuration::from_std(age).ok()) {
+        Some(age) => remove_before(conn, Local::now().naive_local() - age),
+        None => Ok(0),
+    }
+}
+
+/// The `#tags` written in a message, lowercased and without the `#`.
+pub fn message_tags(message: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in message.split(|c: char| c.is_whitespace() || c == ',' || c == '.') {
+        if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+            let tag = tag.to_lowercase();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// A parsed --since/--until value: a moment, or a whole period such as a day or a week.
+struct When {
+    start: NaiveDateTime,
+    /// The end of the period when it was given as one, e.g. tomorrow 00:00 for "today".
+    end: Option<NaiveDateTime>,
+}
+
+fn parse_since(s: &str) -> std::result::Result<NaiveDateTime, String> {
+    parse_when(s, Local::now().naive_local()).map(|w| w.start)
+}
+
+fn parse_until(s: &str) -> std::result::Result<NaiveDateTime, String> {
+    parse_when(s, Local::now().naive_local()).map(|w| w.end.unwrap_or(w.start))
+}
+
+/// Parses a date for --since/--until, relative to `now`:
+/// "now", "today", "yesterday", "tomorrow", a weekday ("monday", the most recent one),
+/// "this/last week", "this/last month", "this/last year", a duration ago ("3d", "2 hours ago"),
+/// or a date/time ("2024-05-01", "2024-05-01 14:00", "2024-05-01T14:00:00").
+fn parse_when(s: &str, now: NaiveDateTime) -> std::result::Result<When, String> {
+    let input = s.trim().to_lowercase();
+    let today = now.date();
+    let day = |date: NaiveDate| When { start: date.and_time(NaiveTime::MIN), end: date.succ_opt().map(|d| d.and_time(NaiveTime::MIN)) };
+    let range = |start: NaiveDate, end: NaiveDate| When { start: start.and_time(NaiveTime::MIN), end: Some(end.and_time(NaiveTime::MIN)) };
+    let month_start = |date: NaiveDate| date.with_day(1).unwrap_or(date);
+    let next_month = |date: NaiveDate| {
+        let first = month_start(date);
+        if first.month() == 12 {
+            NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)
+        } else {
+            first.with_month(first.month() + 1)
+        }
+        .unwrap_or(first)
+    };
+    let week_start = today - ChronoDuration::days(today.weekday().num_days_from_monday() as i64);
+    let year_start = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap_or(today);
+
+    let when = match input.as_str() {
+        "now" => When { start: now, end: None },
+        "today" => day(today),
+        "yesterday" => day(today - ChronoDuration::days(1)),
+        "tomorrow" => day(today + ChronoDuration::days(1)),
+        "this week" | "week" => range(week_start, week_start + ChronoDuration::days(7)),
+        "last week" => range(week_start - ChronoDuration::days(7), week_start),
+        "this month" | "month" => range(month_start(today), next_month(today)),
+        "last month" => {
+            let start = month_start(month_start(today) - ChronoDuration::days(1));
+            range(start, month_start(today))
+        }
+        "this year" | "year" => range(year_start, NaiveDate::from_ymd_opt(today.year() + 1, 1, 1).unwrap_or(today)),
+        "last year" => range(NaiveDate::from_ymd_opt(today.year() - 1, 1, 1).unwrap_or(today), year_start),
+        _ => {
+            if let Some(weekday) = parse_weekday(&input) {
+                let back = (7 + today.weekday().num_days_from_monday() as i64 - weekday.num_days_from_monday() as i64) % 7;
+                // "monday" on a Monday is today, "last monday" is a week ago.
+                let back = if back == 0 && input.starts_with("last ") { 7 } else { back };
+                day(today - ChronoDuration::days(back))
+            } else if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+                day(date)
+            } else if let Some(moment) = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(s.trim(), f).ok())
+            {
+                When { start: moment, end: None }
+            } else {
+                let ago = input.strip_suffix("ago").unwrap_or(&input).trim();
+                let duration = humantime::parse_duration(ago).map_err(|_| {
+                    format!("can't read '{}' as a date; try today, yesterday, monday, last week, 3d, 2 hours ago or 2024-05-01", s)
+                })?;
+                let start = now - ChronoDuration::from_std(duration).map_err(|e| e.to_string())?;
+                When { start, end: None }
+            }
+        }
+    };
+    Ok(when)
+}
+
+fn parse_weekday(s: &str) -> Option<chrono::Weekday> {
+    let s = s.strip_prefix("last ").unwrap_or(s);
+    ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"]
+        .iter()
+        .position(|day| *day == s || (s.len() >= 3 && day.starts_with(s)))
+        .and_then(|i| chrono::Weekday::try_from(i as u8).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    /// Wednesday afternoon.
+    fn now() -> NaiveDateTime {
+        at("2024-05-15 14:30")
+    }
+
+    fn range(input: &str, now: NaiveDateTime) -> (NaiveDateTime, Option<NaiveDateTime>) {
+        let when = parse_when(input, now).unwrap();
+        (when.start, when.end)
+    }
+
+    #[test]
+    fn days() {
+        assert_eq!(range("today", now()), (at("2024-05-15 00:00"), Some(at("2024-05-16 00:00"))));
+        assert_eq!(range("Yesterday", now()), (at("2024-05-14 00:00"), Some(at("2024-05-15 00:00"))));
+        assert_eq!(range("tomorrow", now()), (at("2024-05-16 00:00"), Some(at("2024-05-17 00:00"))));
+        assert_eq!(range("2024-05-01", now()), (at("2024-05-01 00:00"), Some(at("2024-05-02 00:00"))));
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        assert_eq!(range("this week", now()), (at("2024-05-13 00:00"), Some(at("2024-05-20 00:00"))));
+        assert_eq!(range("last week", now()), (at("2024-05-06 00:00"), Some(at("2024-05-13 00:00"))));
+    }
+
+    #[test]
+    fn months_and_years() {
+        assert_eq!(range("this month", now()), (at("2024-05-01 00:00"), Some(at("2024-06-01 00:00"))));
+        assert_eq!(range("last month", now()), (at("2024-04-01 00:00"), Some(at("2024-05-01 00:00"))));
+        assert_eq!(range("this month", at("2024-12-10 09:00")), (at("2024-12-01 00:00"), Some(at("2025-01-01 00:00"))));
+        assert_eq!(range("last month", at("2024-01-10 09:00")), (at("2023-12-01 00:00"), Some(at("2024-01-01 00:00"))));
+        assert_eq!(range("last year", now()), (at("2023-01-01 00:00"), Some(at("2024-01-01 00:00"))));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(range("monday", now()).0, at("2024-05-13 00:00"));
+        assert_eq!(range("fri", now()).0, at("2024-05-10 00:00"));
+        assert_eq!(range("wednesday", now()).0, at("2024-05-15 00:00"));
+        assert_eq!(range("last wednesday", now()).0, at("2024-05-08 00:00"));
+        assert_eq!(range("last monday", now()).0, at("2024-05-13 00:00"));
+        assert!(parse_when("mo", now()).is_err());
+    }
+
+    #[test]
+    fn moments() {
+        assert_eq!(range("now", now()), (now(), None));
+        assert_eq!(range("2024-05-01 14:00", now()), (at("2024-05-01 14:00"), None));
+        assert_eq!(range("2024-05-01T14:00:00", now()), (at("2024-05-01 14:00"), None));
+        assert_eq!(range("3d", now()), (at("2024-05-12 14:30"), None));
+        assert_eq!(range("2 hours ago", now()), (at("2024-05-15 12:30"), None));
+        assert!(parse_when("someday", now()).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+//! `tt history import`: reads history back from the files `tt history export` writes.
+//!
+//! CSV (with a header row), JSON arrays and NDJSON are accepted, using the export's field
+//! names. Only `start` and `duration` (or `duration_seconds`) are required, so files from other
+//! tools can be mapped onto the same columns. Entries already in the history (same start time
+//! and message) are skipped, and each invalid entry is reported with its line number.
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+const OUTCOMES: [&str; 6] = ["done", "stopped", "snoozed", "restarted", "auto-stopped", "auto-snoozed"];
+
+/// An entry as read from the file, before validation. Every field is optional here so that
+/// missing ones can be reported per entry instead of failing the whole file.
+#[derive(Deserialize)]
+struct RawRecord {
+    start: Option<String>,
+    end: Option<String>,
+    duration: Option<String>,
+    duration_seconds: Option<u64>,
+    message: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    project: Option<String>,
+    foreground: Option<bool>,
+    outcome: Option<String>,
+    note: Option<String>,
+}
+
+/// Entries read from a file, each with its line (or, for JSON arrays, its position).
+type Parsed = Vec<(usize, Result<RawRecord, String>)>;
+
+/// A validated entry, ready to insert into timer_history.
+struct Entry {
+    timestamp: String,
+    duration: String,
+    message: String,
+    labels: crate::Labels,
+    fg: bool,
+    outcome: Option<String>,
+    note: Option<String>,
+    finished: Option<String>,
+}
+
+/// Reads a time as written by the export: local "YYYY-MM-DD HH:MM[:SS]" or RFC 3339.
+fn parse_time(value: &str) -> Option<String> {
+    let value = value.trim();
+    let local = DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Local).naive_local())
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        })?;
+    Local.from_local_datetime(&local).earliest()?;
+    Some(local.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+fn validate(raw: RawRecord) -> Result<Entry, String> {
+    let start = raw.start.filter(|s| !s.trim().is_empty()).ok_or("missing start")?;
+    let timestamp = parse_time(&start).ok_or_else(|| format!("start '{}' is not a date", start))?;
+    let duration = match (raw.duration.filter(|d| !d.trim().is_empty()), raw.duration_seconds) {
+        (Some(duration), _) => {
+            humantime::parse_duration(duration.trim()).map_err(|_| format!("duration '{}' is not a duration", duration))?;
+            duration.trim().to_string()
+        }
+        (None, Some(seconds)) => humantime::format_duration(std::time::Duration::from_secs(seconds)).to_string(),
+        (None, None) => return Err("missing duration".into()),
+    };
+    let finished = match raw.end.filter(|e| !e.trim().is_empty()) {
+        Some(end) => Some(parse_time(&end).ok_or_else(|| format!("end '{}' is not a date", end))?),
+        None => None,
+    };
+    let outcome = match raw.outcome.map(|o| o.trim().to_lowercase()).filter(|o| !o.is_empty()) {
+        Some(outcome) if OUTCOMES.contains(&outcome.as_str()) => Some(outcome),
+        Some(outcome) => return Err(format!("unknown outcome '{}' (expected one of {})", outcome, OUTCOMES.join(", "))),
+        None => None,
+    };
+    let message = raw.message.unwrap_or_default();
+    Ok(Entry {
+        timestamp,
+        duration,
+        labels: crate::Labels::new(&raw.tags, raw.project.as_deref(), &message),
+        message,
+        fg: raw.foreground.unwrap_or(false),
+        outcome,
+        note: raw.note.filter(|n| !n.is_empty()),
+        finished,
+    })
+}
+
+/// Splits CSV text into records, each with the line it starts on. Quoted fields may contain
+/// separators, doubled quotes and line breaks.
+fn csv_records(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.is_empty()) {
+                    records.push((start_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                start_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.is_empty()) {
+        records.push((start_line, record));
+    }
+    records
+}
+
+fn parse_csv(text: &str) -> Parsed {
+    let mut records = csv_records(text).into_iter();
+    let Some((_, header)) = records.next() else { return Vec::new() };
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    records
+        .map(|(line, fields)| {
+            let get = |name: &str| {
+                header.iter().position(|h| h == name).and_then(|i| fields.get(i)).map(|v| v.to_string()).filter(|v| !v.is_empty())
+            };
+            let parsed = (|| {
+                let foreground = match get("foreground").map(|v| v.to_lowercase()) {
+                    Some(v) if ["true", "1", "yes"].contains(&v.as_str()) => Some(true),
+                    Some(v) if ["false", "0", "no"].contains(&v.as_str()) => Some(false),
+                    Some(v) => return Err(format!("foreground '{}' is not true or false", v)),
+                    None => None,
+                };
+                let duration_seconds = match get("duration_seconds") {
+                    Some(v) => Some(v.trim().parse().map_err(|_| format!("duration_seconds '{}' is not a number", v))?),
+                    None => None,
+                };
+                Ok(RawRecord {
+                    start: get("start"),
+                    end: get("end"),
+                    duration: get("duration"),
+                    duration_seconds,
+                    message: get("message"),
+                    tags: get("tags").map(|t| t.split([';', ',']).map(str::to_string).collect()).unwrap_or_default(),
+                    project: get("project"),
+                    foreground,
+                    outcome: get("outcome"),
+                    note: get("note"),
+                })
+            })();
+            (line, parsed)
+        })
+        .collect()
+}
+
+fn parse_json(text: &str) -> Result<Parsed, String> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(text).map_err(|e| format!("not a JSON array: {}", e))?;
+    // serde_json doesn't give positions for array items, so number them instead.
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| (i + 1, serde_json::from_value(value).map_err(|e| e.to_string())))
+        .collect())
+}
+
+fn parse_ndjson(text: &str) -> Parsed {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, serde_json::from_str(line).map_err(|e| e.to_string())))
+        .collect()
+}
+
+/// Picks the format from --format, then the file extension, then the first character.
+fn detect_format(format: Option<&str>, path: &Path, text: &str) -> String {
+    if let Some(format) = format {
+        return format.to_string();
+    }
+    match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+        Some("csv") => "csv".into(),
+        Some("json") => "json".into(),
+        Some("ndjson") | Some("jsonl") => "ndjson".into(),
+        _ => match text.trim_start().chars().next() {
+            Some('[') => "json".into(),
+            Some('{') => "ndjson".into(),
+            _ => "csv".into(),
+        },
+    }
+}
+
+fn existing_keys(conn: &Connection) -> rusqlite::Result<HashSet<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT timestamp, coalesce(message, '') FROM timer_history")?;
+    let keys = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    keys.collect()
+}
+
+/// Remembers the entry's start time and message, returning false if either the history or an
+/// earlier line of the file already had them.
+fn is_new(seen: &mut HashSet<(String, String)>, entry: &Entry) -> bool {
+    seen.insert((entry.timestamp.clone(), entry.message.clone()))
+}
+
+pub fn run(path: &Path, format: Option<&str>, dry_run: bool) {
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut text).map(|_| text)
+    } else {
+        std::fs::read_to_string(path)
+    };
+    let text = text.unwrap_or_else(|e| {
+        eprintln!("Could not read {}: {}", path.display(), e);
+        std::process::exit(1);
+    });
+    let format = detect_format(format, path, &text);
+    let (records, unit) = match format.as_str() {
+        "csv" => (parse_csv(&text), "line"),
+        "ndjson" => (parse_ndjson(&text), "line"),
+        "json" => (
+            parse_json(&text).unwrap_or_else(|e| {
+                eprintln!("Could not read {}: {}", path.display(), e);
+                std::process::exit(1);
+            }),
+            "entry",
+        ),
+        other => {
+            eprintln!("Unknown format '{}'. Use csv, json or ndjson.", other);
+            std::process::exit(2);
+        }
+    };
+
+    let mut conn = crate::init_db().unwrap_or_else(|e| {
+        eprintln!("Could not open the timer database: {}", e);
+        std::process::exit(1);
+    });
+    let mut seen = existing_keys(&conn).unwrap_or_default();
+    let mut out = std::io::stdout().lock();
+    let paint = out.is_terminal();
+    // The preview stops once its reader closes the pipe (`--dry-run | head`); the rest still runs.
+    let mut preview = dry_run;
+    let (mut entries, mut duplicates, mut errors) = (Vec::new(), 0, 0);
+    for (position, raw) in records {
+        match raw.and_then(validate) {
+            Ok(entry) => {
+                if is_new(&mut seen, &entry) {
+                    if preview {
+                        preview = writeln!(out, "+ {}  {:<10} {}", entry.timestamp, entry.duration, entry.message).is_ok();
+                    }
+                    entries.push(entry);
+                } else {
+                    duplicates += 1;
+                    if preview {
+                        let line = format!("= {}  {:<10} {} (already in history)", entry.timestamp, entry.duration, entry.message);
+                        let line = if paint { crate::color(&line, "gray") } else { line };
+                        preview = writeln!(out, "{}", line).is_ok();
+                    }
+                }
+            }
+            Err(e) => {
+                errors += 1;
+                eprintln!("{} {}: {}", unit, position, e);
+            }
+        }
+    }
+
+    if !dry_run && !entries.is_empty() {
+        let inserted = conn.transaction().and_then(|tx| {
+            for entry in &entries {
+                tx.execute(
+                    "INSERT INTO timer_history (timestamp, duration, message, fg, outcome, note, finished, project)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![entry.timestamp, entry.duration, entry.message, entry.fg, entry.outcome, entry.note, entry.finished, entry.labels.project],
+                )?;
+                crate::set_history_tags_db(&tx, tx.last_insert_rowid(), &entry.labels.tags)?;
+            }
+            tx.commit()
+        });
+        if let Err(e) = inserted {
+            eprintln!("Could not import: {}", e);
+            std::process::exit(1);
+        }
+    }
+    let _ = writeln!(
+        out,
+        "{} {} timers, skipped {} already in history, {} invalid.",
+        if dry_run { "Would import" } else { "Imported" },
+        entries.len(),
+        duplicates,
+        errors
+    );
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quoted_fields() {
+        let records = csv_records("start,message\n2024-05-01 09:00,\"Call Ann, Bob\"\n2024-05-02 09:00,\"say \"\"hi\"\"\",x\n");
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].1, vec!["2024-05-01 09:00", "Call Ann, Bob"]);
+        assert_eq!(records[2].1, vec!["2024-05-02 09:00", "say \"hi\"", "x"]);
+    }
+
+    #[test]
+    fn csv_line_breaks_keep_line_numbers() {
+        let text = "\u{feff}start,message\r\n2024-05-01 09:00,\"first\nsecond\"\r\n\r\n2024-05-02 09:00,next";
+        let records = csv_records(text);
+        assert_eq!(records.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![1, 2, 5]);
+        assert_eq!(records[0].1, vec!["start", "message"]);
+        assert_eq!(records[1].1[1], "first\nsecond");
+        assert_eq!(records[2].1, vec!["2024-05-02 09:00", "next"]);
+    }
+
+    #[test]
+    fn csv_columns_by_header() {
+        let parsed = parse_csv("Message,Start,Duration,Tags,Foreground\nwrite,2024-05-01 09:00,25m,deep;work,yes\nx,2024-05-01 10:00,5m,,maybe\n");
+        let (line, first) = &parsed[0];
+        let first = first.as_ref().unwrap();
+        assert_eq!(*line, 2);
+        assert_eq!(first.message.as_deref(), Some("write"));
+        assert_eq!(first.tags, vec!["deep", "work"]);
+        assert_eq!(first.foreground, Some(true));
+        assert_eq!(parsed[1].0, 3);
+        assert!(parsed[1].1.is_err());
+    }
+
+    fn entry(json: &str) -> Entry {
+        validate(serde_json::from_str(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn duplicates_by_start_and_message() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE timer_history (timestamp TEXT, message TEXT);
+             INSERT INTO timer_history VALUES ('2024-05-01 09:00:00', NULL);",
+        )
+        .unwrap();
+        let mut seen = existing_keys(&conn).unwrap();
+        // Same start written differently, and a missing message matching a NULL one.
+        assert!(!is_new(&mut seen, &entry(r#"{"start": "2024-05-01T09:00", "duration": "5m"}"#)));
+        assert!(is_new(&mut seen, &entry(r#"{"start": "2024-05-01 09:00", "duration": "5m", "message": "tea"}"#)));
+        // A repeat within the same file.
+        assert!(!is_new(&mut seen, &entry(r#"{"start": "2024-05-01 09:00:00", "duration": "10m", "message": "tea"}"#)));
+    }
+}
@@ -0,0 +1,162 @@
+//! `tt list`: prints the active timers once and exits, for scripts and status bars.
+//!
+//! `--format` picks the output: `table` (default), `json`, `csv`, `plain`, or a template such as
+//! `'{id} {left} {message}'` where each `{field}` is replaced with that timer's value.
+
+use crate::{color, csv_field, format_countdown, ActiveTimer, TimerState};
+use chrono::Local;
+use serde::Serialize;
+use std::io::IsTerminal;
+
+/// Field names, in the order used by the CSV header and listed in the template help.
+const FIELDS: [&str; 11] = ["id", "pid", "duration", "message", "tags", "project", "state", "left", "remaining", "ends", "percent"];
+
+/// One active timer as printed by `tt list`.
+#[derive(Serialize)]
+struct Row {
+    id: i64,
+    pid: i32,
+    duration: String,
+    message: String,
+    tags: Vec<String>,
+    project: Option<String>,
+    /// "running", "paused" or "ringing".
+    state: TimerState,
+    /// Time left as MM:SS, or H:MM:SS from an hour up.
+    left: String,
+    /// Time left in seconds.
+    remaining: u64,
+    /// Local time the timer goes off, if it is running.
+    ends: Option<String>,
+    /// How much of the countdown has elapsed, 0-100.
+    percent: u8,
+}
+
+impl Row {
+    fn new(timer: &ActiveTimer) -> Self {
+        let state = timer.state();
+        let ends = (state == TimerState::Running).then(|| {
+            let end = Local::now() + chrono::Duration::from_std(timer.remaining).unwrap_or_default();
+            end.format("%H:%M:%S").to_string()
+        });
+        Row {
+            id: timer.id,
+            pid: timer.pid,
+            duration: timer.duration.clone(),
+            message: timer.message.clone(),
+            tags: timer.tags.clone(),
+            project: timer.project.clone(),
+            state,
+            left: format_countdown(timer.remaining),
+            remaining: timer.remaining.as_secs(),
+            ends,
+            percent: timer.percent(),
+        }
+    }
+
+    fn field(&self, name: &str) -> Option<String> {
+        Some(match name {
+            "id" => self.id.to_string(),
+            "pid" => self.pid.to_string(),
+            "duration" => self.duration.clone(),
+            "message" => self.message.clone(),
+            "tags" => self.tags.join(";"),
+            "project" => self.project.clone().unwrap_or_default(),
+            "state" => self.state.as_str().to_string(),
+            "left" => self.left.clone(),
+            "remaining" => self.remaining.to_string(),
+            "ends" => self.ends.clone().unwrap_or_default(),
+            "percent" => self.percent.to_string(),
+            _ => return None,
+        })
+    }
+}
+
+/// Replaces each `{field}` in the template. Unknown names are left as they are.
+fn render(template: &str, row: &Row) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}').and_then(|end| Some((end, row.field(&after[..end])?))) {
+            Some((end, value)) => {
+                out.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn print_table(rows: &[Row]) {
+    if rows.is_empty() {
+        println!("No active timers.");
+        return;
+    }
+    let paint = std::io::stdout().is_terminal();
+    println!("{:<5} {:<10} {:<8} {:<9} {:<8} MESSAGE", "ID", "DURATION", "STATE", "LEFT", "ENDS");
+    for row in rows {
+        let state = format!("{:<8}", row.state.as_str());
+        let state = match (paint, row.state) {
+            (false, _) => state,
+            (true, TimerState::Ringing) => color(&state, "red"),
+            (true, TimerState::Paused) => color(&state, "yellow"),
+            (true, TimerState::Running) => color(&state, "green"),
+        };
+        println!(
+            "{:<5} {:<10} {} {:<9} {:<8} {}",
+            row.id,
+            row.duration,
+            state,
+            row.left,
+            row.ends.as_deref().unwrap_or("-"),
+            crate::labeled_message(&row.message, &row.tags, row.project.as_deref())
+        );
+    }
+}
+
+pub fn run(format: &str) {
+    let timers = crate::init_db().and_then(|conn| crate::load_active_timers_db(&conn));
+    let timers = match timers {
+        Ok(timers) => timers,
+        Err(e) => {
+            eprintln!("Could not read active timers: {}", e);
+            std::process::exit(1);
+        }
+    };
+    // Skip rows left behind by a timer process that was killed.
+    let rows: Vec<Row> = timers.iter().filter(|t| crate::process_alive(t.pid)).map(Row::new).collect();
+
+    match format {
+        "table" => print_table(&rows),
+        "json" => println!("{}", serde_json::to_string_pretty(&rows).unwrap_or_else(|_| "[]".into())),
+        "csv" => {
+            println!("{}", FIELDS.join(","));
+            for row in &rows {
+                let fields: Vec<String> = FIELDS.iter().map(|f| csv_field(&row.field(f).unwrap_or_default())).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+        "plain" => {
+            for row in &rows {
+                println!("{}", render("{id}\t{state}\t{left}\t{message}", row));
+            }
+        }
+        template if template.contains('{') => {
+            for row in &rows {
+                println!("{}", render(template, row));
+            }
+        }
+        other => {
+            eprintln!("Unknown format '{}'. Use table, json, csv, plain or a template like '{{id}} {{left}} {{message}}'.", other);
+            eprintln!("Template fields: {}", FIELDS.map(|f| format!("{{{}}}", f)).join(" "));
+            std::process::exit(2);
+        }
+    }
+}
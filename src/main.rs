@@ -1,20 +1,31 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use chrono::{Local, TimeZone};
 use humantime::{parse_duration};
 use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
 use std::thread::sleep;
 use std::time::Duration;
 use std::process;
-use std::io::{Write, Cursor, BufRead};
+use std::io::{Read, Write};
 use std::process::Command;
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
-#[cfg(unix)]
-use std::os::unix::process::CommandExt as UnixCommandExt;
 use eframe::{egui, App};
 use egui::{Color32, FontId, TextFormat, WidgetText};
 use egui::text::LayoutJob;
 
+mod dashboard;
+mod export;
+mod history;
+mod import;
+mod list;
+mod prompt;
+mod report;
+mod sounds;
+mod status;
+mod theme;
+mod tray;
+
 /// Cross-platform process termination
 fn kill_process(pid: i32) {
     #[cfg(unix)]
@@ -39,7 +50,30 @@ fn kill_process(pid: i32) {
     }
 }
 
-fn styled_button_label(shortcut: &str, color: Color32, label: &str) -> WidgetText {
+/// Whether a process is still running, used to clean up timers whose process was killed.
+fn process_alive(pid: i32) -> bool {
+    #[cfg(unix)]
+    {
+        // Signal 0 only checks that the process exists; EPERM means it exists but isn't ours.
+        unsafe { libc::kill(pid, 0) == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM) }
+    }
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid as u32);
+            if handle == 0 {
+                return false;
+            }
+            let mut code = 0u32;
+            let ok = GetExitCodeProcess(handle, &mut code);
+            windows_sys::Win32::Foundation::CloseHandle(handle);
+            ok != 0 && code == windows_sys::Win32::Foundation::STILL_ACTIVE as u32
+        }
+    }
+}
+
+fn styled_button_label(shortcut: &str, color: Color32, label: &str, text_color: Color32) -> WidgetText {
     let mut job = LayoutJob::default();
 
     // Style the shortcut portion, e.g. "[ z ]"
@@ -59,7 +93,7 @@ fn styled_button_label(shortcut: &str, color: Color32, label: &str) -> WidgetTex
         0.0,
         TextFormat {
             font_id: FontId::proportional(16.0),
-            color: Color32::WHITE,
+            color: text_color,
             ..Default::default()
         },
     );
@@ -83,6 +117,87 @@ fn get_snooze_duration_and_str() -> (Duration, String) {
     }
 }
 
+/// Returns the snooze presets offered by the popup, from the comma separated TT_SNOOZE_PRESETS
+/// environment variable (default "1m,5m,10m"). Unparseable entries are skipped and at most nine
+/// are kept so each one has a number key.
+fn get_snooze_presets() -> Vec<(Duration, String)> {
+    let presets = std::env::var("TT_SNOOZE_PRESETS").unwrap_or_else(|_| "1m,5m,10m".to_string());
+    presets
+        .split(',')
+        .map(str::trim)
+        .filter_map(|s| parse_duration(s).ok().map(|d| (d, s.to_string())))
+        .take(9)
+        .collect()
+}
+
+/// Returns a duration parsed from the given environment variable, or None if it is
+/// unset, unparseable, or zero.
+fn get_env_duration(env_var: &str) -> Option<Duration> {
+    std::env::var(env_var)
+        .ok()
+        .and_then(|s| parse_duration(&s).ok())
+        .filter(|d| !d.is_zero())
+}
+
+/// Escalation policy for an unanswered alarm, read from environment variables:
+///   TT_FADE_IN          - fade the alarm volume in from silent over this duration
+///   TT_REALERT_INTERVAL - re-raise the popup every interval while it is unanswered
+///   TT_MAX_RING         - give up ringing after this duration
+///   TT_MAX_RING_ACTION  - what to do when TT_MAX_RING is reached: "stop" (default) or "snooze"
+struct EscalationPolicy {
+    fade_in: Option<Duration>,
+    realert_interval: Option<Duration>,
+    max_ring: Option<Duration>,
+    snooze_on_max_ring: bool,
+}
+
+fn get_escalation_policy() -> EscalationPolicy {
+    EscalationPolicy {
+        fade_in: get_env_duration("TT_FADE_IN"),
+        realert_interval: get_env_duration("TT_REALERT_INTERVAL"),
+        max_ring: get_env_duration("TT_MAX_RING"),
+        snooze_on_max_ring: std::env::var("TT_MAX_RING_ACTION")
+            .map(|s| s.eq_ignore_ascii_case("snooze"))
+            .unwrap_or(false),
+    }
+}
+
+/// Default popup window size in points.
+const POPUP_SIZE: (f32, f32) = (400.0, 550.0);
+
+/// Where and how the popup window is shown, read from environment variables:
+///   TT_POPUP_ON_TOP     - "1" keeps the popup above other windows
+///   TT_POPUP_POSITION   - "center" (default), "top-left", "top-right", "bottom-left",
+///                         "bottom-right" on the primary monitor, or "X,Y" in screen points
+///   TT_POPUP_SIZE       - window size, e.g. "500x600"
+///   TT_POPUP_FULLSCREEN - "1" covers the whole screen (same as `--block`)
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PopupPlacement {
+    pub on_top: bool,
+    pub position: String,
+    pub size: Option<(f32, f32)>,
+    pub fullscreen: bool,
+}
+
+fn get_popup_placement() -> PopupPlacement {
+    let flag = |name: &str| std::env::var(name).map(|v| v == "1").unwrap_or(false);
+    PopupPlacement {
+        on_top: flag("TT_POPUP_ON_TOP"),
+        position: std::env::var("TT_POPUP_POSITION").unwrap_or_else(|_| "center".to_string()).to_lowercase(),
+        size: std::env::var("TT_POPUP_SIZE").ok().and_then(|s| parse_pair(&s, 'x')),
+        fullscreen: flag("TT_POPUP_FULLSCREEN"),
+    }
+}
+
+/// Parses two positive numbers separated by `sep`, e.g. "500x600" or "1920,0".
+fn parse_pair(s: &str, sep: char) -> Option<(f32, f32)> {
+    let (a, b) = s.split_once(sep)?;
+    let a = a.trim().parse::<f32>().ok().filter(|v| *v >= 0.0)?;
+    let b = b.trim().parse::<f32>().ok().filter(|v| *v >= 0.0)?;
+    Some((a, b))
+}
+
 /// Get configured key for an action from environment variable, with fallback default
 fn get_action_key(env_var: &str, default: egui::Key) -> egui::Key {
     if let Ok(key_str) = std::env::var(env_var) {
@@ -141,9 +256,17 @@ fn get_action_key_char(env_var: &str, default: char) -> char {
 ///
 /// Check for updates with:
 ///   timer_cli --update or timer_cli -u
+///
+/// List and preview alert sounds with:
+///   timer_cli sounds list | timer_cli sounds preview <NAME>
+///
+/// Show a system tray icon (Linux) with:
+///   timer_cli tray
 #[derive(Parser)]
 #[command(author, about, version)]
 #[command(disable_version_flag = true)]
+// Once a duration is given, words like "list" are the message rather than a subcommand.
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
     /// Print version information
     #[arg(short = 'v', long = "version", action = clap::ArgAction::Version)]
@@ -157,6 +280,10 @@ struct Args {
     #[arg(short = 'l', long = "logs", value_name = "COUNT", num_args = 0..=1, default_missing_value = "10")]
     logs: Option<usize>,
 
+    /// Only show log entries whose message or note contains TEXT (implies --logs)
+    #[arg(long, value_name = "TEXT")]
+    search: Option<String>,
+
     /// Show a live view of active timers.
     #[arg(short='a', long)]
     active: bool,
@@ -175,9 +302,156 @@ struct Args {
     #[arg(short, long, default_value_t = false)]
     fg: bool,
 
+    /// Tag the timer (repeatable). #tags written in the message are added too.
+    #[arg(short = 't', long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    /// Project the timer belongs to.
+    #[arg(short = 'p', long, value_name = "NAME")]
+    project: Option<String>,
+
+    /// Alert sound to play (see `sounds list`). Defaults to TT_SOUND or "loop".
+    #[arg(long, value_name = "NAME")]
+    sound: Option<String>,
+
+    /// Speak the message aloud when the timer fires (also enabled by TT_SAY=1).
+    #[arg(long, default_value_t = false)]
+    say: bool,
+
+    /// Show the popup fullscreen and above all windows, e.g. for break reminders (also TT_POPUP_FULLSCREEN=1).
+    #[arg(long, default_value_t = false)]
+    block: bool,
+
     /// Internal flag: indicates this process was spawned as a background child (hidden from help).
     #[arg(long, hide = true, default_value_t = false)]
     background_child: bool,
+
+    /// Internal flag: timer_history row logged by the parent for a background child (hidden from help).
+    #[arg(long, hide = true)]
+    history_id: Option<i64>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List or preview the built-in alert sounds
+    Sounds {
+        #[command(subcommand)]
+        action: Option<SoundsCommand>,
+    },
+    /// Run a system tray icon with the nearest countdown and a menu of active timers (Linux only)
+    Tray,
+    /// Print the active timers once and exit, for scripts and status bars
+    List {
+        /// table, json, csv, plain, or a template such as '{id} {left} {message}'
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+    /// Print a one-line status for the nearest timer, for tmux, waybar and polybar
+    Status {
+        /// text, or waybar for waybar's JSON (text, tooltip, class, percentage)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+        /// Keep running and print a new line whenever the status changes
+        #[arg(short, long)]
+        watch: bool,
+        /// How often to check for changes with --watch
+        #[arg(long, default_value = "1s", value_parser = humantime::parse_duration)]
+        interval: Duration,
+    },
+    /// Start a past timer again with the same duration, message, tags and options
+    Again {
+        /// History id from `tt history` or `tt -l` (default: the last timer)
+        id: Option<i64>,
+        /// Run it in the foreground even if it first ran in the background
+        #[arg(short, long)]
+        fg: bool,
+        /// Run it in the background even if it first ran in the foreground
+        #[arg(short, long, conflicts_with = "fg")]
+        bg: bool,
+    },
+    /// Search the timer history by date, text, #tag, outcome and more
+    #[command(args_conflicts_with_subcommands = true)]
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryCommand>,
+        #[command(flatten)]
+        filter: Box<history::HistoryFilter>,
+        #[command(flatten)]
+        paging: history::Paging,
+    },
+    /// Summarize focus time, outcomes, snoozes and streaks from the history
+    Report {
+        /// Group focus time by day, week, month, tag, project or message (the text before ':')
+        #[arg(short, long, default_value = "day")]
+        by: String,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        /// Show a calendar heatmap of focus time per day over the last year
+        #[arg(long)]
+        heatmap: bool,
+        #[command(flatten)]
+        filter: Box<history::HistoryFilter>,
+    },
+    /// Print the nearest timer for a shell prompt, read from a cache file (fast, no database)
+    Prompt,
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// Export the history (all of it, or what the filters match) for spreadsheets, calendars or time trackers
+    Export {
+        /// csv, json, ndjson or ics
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+        /// Write to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+        #[command(flatten)]
+        filter: Box<history::HistoryFilter>,
+        #[command(flatten)]
+        paging: history::Paging,
+    },
+    /// Import history from a CSV, JSON or NDJSON file in the export format ("-" reads stdin)
+    Import {
+        file: std::path::PathBuf,
+        /// csv, json or ndjson (default: from the file extension or contents)
+        #[arg(short, long)]
+        format: Option<String>,
+        /// Show what would be imported without changing the history
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+    /// Delete history entries by id (the ID column of `tt history`)
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Delete history entries older than a duration, e.g. 90d
+    Prune {
+        #[arg(long, value_name = "AGE", value_parser = humantime::parse_duration)]
+        older_than: Duration,
+    },
+    /// Delete the whole history
+    Clear {
+        /// Confirm deleting every entry
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SoundsCommand {
+    /// List the built-in alert sounds (default)
+    List,
+    /// Play a sound once so you can hear it
+    Preview {
+        /// Sound name, e.g. "chime"
+        name: String,
+    },
 }
 
 /// Returns the path to the SQLite database.
@@ -192,12 +466,36 @@ fn db_path() -> String {
     }
 }
 
-/// Initialize the database and create tables if they do not exist.
+/// Schema version recorded in PRAGMA user_version. Bump it when `migrate_db` gains a step, so
+/// existing databases run the migration once more.
+const SCHEMA_VERSION: i64 = 3;
+
+/// Open the database, creating or upgrading its tables the first time a new version of tt
+/// opens it.
+fn init_db() -> Result<Connection> {
+    let conn = Connection::open(db_path())?;
+    // Several tt processes share the file; wait for a lock instead of failing at once.
+    conn.busy_timeout(Duration::from_secs(5))?;
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version < SCHEMA_VERSION {
+        migrate_db(&conn)?;
+    }
+    Ok(conn)
+}
+
+/// Create the tables if they do not exist and add the columns older versions lacked. Every step
+/// can run again safely, so any older database is brought up to date by running them all.
 ///
 /// The active_timers table uses an autoincrement primary key (id)
 /// and stores the process id (pid) separately.
-fn init_db() -> Result<Connection> {
-    let conn = Connection::open(db_path())?;
+fn migrate_db(conn: &Connection) -> Result<()> {
+    // Another tt may be migrating at the same time; let one finish before the other checks.
+    let tx = rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)?;
+    let version: i64 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+    let conn = &*tx;
     conn.execute(
         "CREATE TABLE IF NOT EXISTS timer_history (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -208,6 +506,9 @@ fn init_db() -> Result<Connection> {
          )",
          [],
     )?;
+    add_column_if_missing(conn, "timer_history", "outcome", "TEXT")?;
+    add_column_if_missing(conn, "timer_history", "note", "TEXT")?;
+    add_column_if_missing(conn, "timer_history", "finished", "TEXT")?;
     conn.execute(
         "CREATE TABLE IF NOT EXISTS active_timers (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -218,91 +519,330 @@ fn init_db() -> Result<Connection> {
          )",
          [],
     )?;
-    Ok(conn)
+    add_column_if_missing(conn, "active_timers", "created", "TEXT")?;
+    add_column_if_missing(conn, "active_timers", "snoozes", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "active_timers", "paused_at", "TEXT")?;
+    add_column_if_missing(conn, "active_timers", "fired_at", "TEXT")?;
+    add_column_if_missing(conn, "active_timers", "action", "TEXT")?;
+    add_column_if_missing(conn, "timer_history", "project", "TEXT")?;
+    add_column_if_missing(conn, "active_timers", "project", "TEXT")?;
+    add_column_if_missing(conn, "timer_history", "sound", "TEXT")?;
+    add_column_if_missing(conn, "timer_history", "say", "INTEGER")?;
+    add_column_if_missing(conn, "timer_history", "block", "INTEGER")?;
+    add_column_if_missing(conn, "active_timers", "flag_tags", "TEXT")?;
+    add_column_if_missing(conn, "timer_history", "run_id", "INTEGER")?;
+    add_column_if_missing(conn, "active_timers", "history_id", "INTEGER")?;
+    let tags_exist: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'timer_tags')",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+         );
+         CREATE TABLE IF NOT EXISTS timer_tags (
+            history_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (history_id, tag_id)
+         );
+         CREATE TABLE IF NOT EXISTS active_timer_tags (
+            active_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (active_id, tag_id)
+         );",
+    )?;
+    if !tags_exist {
+        backfill_tags_db(conn)?;
+    }
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()
+}
+
+/// Tags older versions only wrote as #tags in messages; link them once when the tag tables
+/// are first created.
+fn backfill_tags_db(conn: &Connection) -> Result<()> {
+    let history: Vec<(i64, String)> = conn
+        .prepare("SELECT id, message FROM timer_history WHERE message LIKE '%#%'")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_>>()?;
+    for (id, message) in history {
+        set_history_tags_db(conn, id, &history::message_tags(&message))?;
+    }
+    Ok(())
+}
+
+/// Adds a column to an existing table if it is not there yet, so databases
+/// created by older versions keep working.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
+    }
+    Ok(())
+}
+
+/// Tags and project of a timer, from --tag/--project and #tags written in the message.
+#[derive(Clone, Default)]
+struct Labels {
+    tags: Vec<String>,
+    project: Option<String>,
+    /// Just the --tag ones, so the tags can be worked out again when the message is edited.
+    flag_tags: Vec<String>,
+}
+
+impl Labels {
+    fn new(tags: &[String], project: Option<&str>, message: &str) -> Self {
+        let mut flag_tags: Vec<String> = Vec::new();
+        for tag in tags.iter().map(|t| normalize_tag(t)) {
+            if !tag.is_empty() && !flag_tags.contains(&tag) {
+                flag_tags.push(tag);
+            }
+        }
+        let mut all = flag_tags.clone();
+        for tag in history::message_tags(message) {
+            if !all.contains(&tag) {
+                all.push(tag);
+            }
+        }
+        Labels {
+            tags: all,
+            project: project.map(str::trim).filter(|p| !p.is_empty()).map(str::to_string),
+            flag_tags,
+        }
+    }
+}
+
+/// Tags are stored lowercase and without the leading '#'. Spaces (and the other characters
+/// that end a #tag in a message) become '-', so `-t "deep work"` is the single tag deep-work.
+fn normalize_tag(tag: &str) -> String {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+    tag.split(|c: char| c.is_whitespace() || c == ',' || c == '.').filter(|w| !w.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+/// A message as shown in lists: "[project] message #tag", adding the tags that the message
+/// doesn't already mention.
+fn labeled_message(message: &str, tags: &[String], project: Option<&str>) -> String {
+    let mentioned = history::message_tags(message);
+    let mut text = match project {
+        Some(project) => format!("[{}] {}", project, message),
+        None => message.to_string(),
+    };
+    for tag in tags.iter().filter(|t| !mentioned.contains(t)) {
+        text.push_str(&format!(" #{}", tag));
+    }
+    text.trim().to_string()
+}
+
+/// The ids of the given tags, creating the ones that don't exist yet.
+fn tag_ids_db(conn: &Connection, tags: &[String]) -> Result<Vec<i64>> {
+    let mut ids = Vec::new();
+    for tag in tags {
+        conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])?;
+        ids.push(conn.query_row("SELECT id FROM tags WHERE name = ?1", params![tag], |row| row.get(0))?);
+    }
+    Ok(ids)
+}
+
+/// Links a timer_history row to its tags.
+fn set_history_tags_db(conn: &Connection, history_id: i64, tags: &[String]) -> Result<()> {
+    for tag_id in tag_ids_db(conn, tags)? {
+        conn.execute(
+            "INSERT OR IGNORE INTO timer_tags (history_id, tag_id) VALUES (?1, ?2)",
+            params![history_id, tag_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Links an active timer to its tags, replacing the ones it had.
+fn set_active_timer_tags_db(conn: &Connection, active_id: i64, tags: &[String]) -> Result<()> {
+    conn.execute("DELETE FROM active_timer_tags WHERE active_id = ?1", params![active_id])?;
+    for tag_id in tag_ids_db(conn, tags)? {
+        conn.execute(
+            "INSERT OR IGNORE INTO active_timer_tags (active_id, tag_id) VALUES (?1, ?2)",
+            params![active_id, tag_id],
+        )?;
+    }
+    Ok(())
 }
 
-/// Log a timer creation into the timer_history table.
-fn log_timer_creation_db(conn: &Connection, duration: &str, message: &str, fg: bool) -> Result<()> {
+/// Log a timer creation into the timer_history table, with its tags, project and alarm
+/// options (so `tt again` can repeat it). Snoozes and restarts pass the entry of the timer they
+/// continue as `run_id`. Returns the newly inserted record’s id.
+fn log_timer_creation_db(conn: &Connection, duration: &str, message: &str, fg: bool, labels: &Labels, alarm: &AlarmOptions, run_id: Option<i64>) -> Result<i64> {
     let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     conn.execute(
-        "INSERT INTO timer_history (timestamp, duration, message, fg) VALUES (?1, ?2, ?3, ?4)",
-        params![timestamp, duration, message, fg],
+        "INSERT INTO timer_history (timestamp, duration, message, fg, project, sound, say, block, run_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![timestamp, duration, message, fg, labels.project, alarm.sound_given.then_some(alarm.sound.name), alarm.say, alarm.block, run_id],
+    )?;
+    let id = conn.last_insert_rowid();
+    set_history_tags_db(conn, id, &labels.tags)?;
+    Ok(id)
+}
+
+/// Record how a timer ended (e.g. "stopped", "snoozed", "auto-stopped") and when, on its
+/// timer_history row.
+fn set_timer_outcome_db(conn: &Connection, history_id: i64, outcome: &str) -> Result<()> {
+    let finished = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "UPDATE timer_history SET outcome = ?1, finished = ?2 WHERE id = ?3",
+        params![outcome, finished, history_id],
+    )?;
+    Ok(())
+}
+
+/// Attach a note (from the popup's Done action) to a timer_history row.
+fn set_timer_note_db(conn: &Connection, history_id: i64, note: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE timer_history SET note = ?1 WHERE id = ?2",
+        params![note, history_id],
     )?;
     Ok(())
 }
 
-/// Display the last `count` entries from the timer_history table.
-fn show_history_db(count: usize) -> Result<()> {
+/// Display a page of the timer_history entries matching `filter` (newest first unless reversed).
+/// Returns whether there are more pages.
+fn show_history_db(filter: &history::HistoryFilter, paging: &history::Paging) -> Result<bool> {
     use textwrap::{fill, Options};
 
     let conn = init_db()?;
-    let mut stmt = conn.prepare(
-        "SELECT timestamp, duration, message, fg FROM timer_history ORDER BY id DESC LIMIT ?1"
-    )?;
-    let history_iter = stmt.query_map(params![count as i64], |row| {
-        Ok((
-            row.get::<_, String>(0)?,
-            row.get::<_, String>(1)?,
-            row.get::<_, String>(2)?,
-            row.get::<_, bool>(3)?,
-        ))
-    })?;
+    let (entries, more) = history::query_page(&conn, filter, paging)?;
 
     // Set maximum column widths
+    let id_width = entries.iter().map(|e| e.id.to_string().len()).max().unwrap_or(0).max(2);
     let timestamp_width = 20;
     let duration_width = 12;
     let message_width = 40;
+    let fg_width = 10;
 
     println!(
-        "{:<timestamp_width$} | {:<duration_width$} | {:<message_width$} | {}",
+        "{:>id_width$} | {:<timestamp_width$} | {:<duration_width$} | {:<message_width$} | {:<fg_width$} | Outcome",
+        "ID",
         "Timestamp",
         "Duration",
         "Message",
         "Foreground",
+        id_width = id_width,
         timestamp_width = timestamp_width,
         duration_width = duration_width,
-        message_width = message_width
+        message_width = message_width,
+        fg_width = fg_width
     );
-    println!("{}", "-".repeat(timestamp_width + duration_width + message_width + 20));
-
-    for entry in history_iter {
-        let (timestamp, duration, message, fg) = entry?;
-        // Wrap the duration and message to the desired widths
-        let wrapped_duration = fill(&duration, Options::new(duration_width));
-        let wrapped_message = fill(&message, Options::new(message_width));
+    println!("{}", "-".repeat(id_width + timestamp_width + duration_width + message_width + fg_width + 25));
+
+    for entry in entries.iter() {
+        let history::HistoryEntry { id, timestamp, duration, message, fg, outcome, note, tags, project, .. } = entry;
+        // Wrap the duration and message to the desired widths. A note goes under the message.
+        let wrapped_duration = fill(duration, Options::new(duration_width));
+        let mut wrapped_message = fill(&labeled_message(message, tags, project.as_deref()), Options::new(message_width));
+        if let Some(note) = note.as_deref().filter(|n| !n.is_empty()) {
+            wrapped_message.push('\n');
+            wrapped_message.push_str(&fill(&format!("✎ {}", note), Options::new(message_width)));
+        }
 
         // Split wrapped text into lines so we can print multiple lines if needed.
         let duration_lines: Vec<&str> = wrapped_duration.lines().collect();
         let message_lines: Vec<&str> = wrapped_message.lines().collect();
         let num_lines = duration_lines.len().max(message_lines.len()).max(1);
 
-        // Print first line with timestamp and foreground flag
+        // Print first line with id, timestamp, foreground flag and outcome
         println!(
-            "{:<timestamp_width$} | {:<duration_width$} | {:<message_width$} | {}",
+            "{:>id_width$} | {:<timestamp_width$} | {:<duration_width$} | {:<message_width$} | {:<fg_width$} | {}",
+            id,
             timestamp,
-            duration_lines.get(0).unwrap_or(&""),
-            message_lines.get(0).unwrap_or(&""),
+            duration_lines.first().unwrap_or(&""),
+            message_lines.first().unwrap_or(&""),
             fg,
+            outcome.as_deref().unwrap_or_default(),
+            id_width = id_width,
             timestamp_width = timestamp_width,
             duration_width = duration_width,
             message_width = message_width,
+            fg_width = fg_width,
         );
 
-        // For additional wrapped lines, print empty strings for timestamp and foreground columns.
+        // For additional wrapped lines, print empty strings for id, timestamp, foreground and outcome columns.
         for i in 1..num_lines {
             println!(
-                "{:<timestamp_width$} | {:<duration_width$} | {:<message_width$} | {}",
+                "{:>id_width$} | {:<timestamp_width$} | {:<duration_width$} | {:<message_width$} | {:<fg_width$} | ",
+                "",
                 "",
                 duration_lines.get(i).unwrap_or(&""),
                 message_lines.get(i).unwrap_or(&""),
                 "",
+                id_width = id_width,
                 timestamp_width = timestamp_width,
                 duration_width = duration_width,
                 message_width = message_width,
+                fg_width = fg_width,
             );
         }
     }
-    Ok(())
+    Ok(more)
+}
+
+/// A past timer as `tt again` starts it again.
+struct RepeatTimer {
+    duration: String,
+    message: String,
+    fg: bool,
+    labels: Labels,
+    sound: Option<String>,
+    say: bool,
+    block: bool,
+}
+
+/// The timer `tt again` repeats for a history entry (the given id, or the most recent one):
+/// the entry itself, or for a snooze or restart the entry that started the timer. Returns the
+/// entry's id and the timer's, or None if there is no such entry.
+fn repeat_run_db(conn: &Connection, id: Option<i64>) -> Result<Option<(i64, i64)>> {
+    let run = conn.query_row(
+        "SELECT id, coalesce(run_id, id) FROM timer_history WHERE id = ?1 OR ?1 IS NULL
+         ORDER BY timestamp DESC, id DESC LIMIT 1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    );
+    match run {
+        Ok(run) => Ok(Some(run)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads the timer_history entry with the given id for `tt again`.
+fn repeat_timer_db(conn: &Connection, id: i64) -> Result<Option<RepeatTimer>> {
+    let mut stmt = conn.prepare(
+        "SELECT duration, message, fg, project, sound, say, block,
+                (SELECT group_concat(name, ' ') FROM timer_tags JOIN tags ON tags.id = tag_id WHERE history_id = timer_history.id)
+         FROM timer_history
+         WHERE id = ?1",
+    )?;
+    let mut rows = stmt.query(params![id])?;
+    let Some(row) = rows.next()? else { return Ok(None) };
+    let message: String = row.get::<_, Option<String>>(1)?.unwrap_or_default();
+    let message = message.as_str();
+    // The history doesn't say which tags were given with --tag; those not in the message must have been.
+    let mentioned = history::message_tags(message);
+    let tags: Vec<String> = row
+        .get::<_, Option<String>>(7)?
+        .map(|t| t.split(' ').filter(|t| !mentioned.iter().any(|m| m == t)).map(str::to_string).collect())
+        .unwrap_or_default();
+    Ok(Some(RepeatTimer {
+        duration: row.get(0)?,
+        message: message.to_string(),
+        fg: row.get::<_, Option<bool>>(2)?.unwrap_or(false),
+        labels: Labels::new(&tags, row.get::<_, Option<String>>(3)?.as_deref(), message),
+        sound: row.get(4)?,
+        say: row.get::<_, Option<bool>>(5)?.unwrap_or(false),
+        block: row.get::<_, Option<bool>>(6)?.unwrap_or(false),
+    }))
 }
 
 /// Kill all active timers without entering the interactive view
@@ -320,10 +860,10 @@ fn kill_all_timers() -> Result<()> {
     }
     
     let count = timers.len();
-    for (_id, pid) in &timers {
+    for (id, pid) in &timers {
         kill_process(*pid);
+        unregister_active_timer_db(&conn, *id)?;
     }
-    conn.execute("DELETE FROM active_timers", [])?;
     
     println!("{} Killed {} active timer{}.", 
         color("✓", "green"),
@@ -333,24 +873,394 @@ fn kill_all_timers() -> Result<()> {
     Ok(())
 }
 
-/// Inserts a new active timer record into active_timers.
-/// Returns the newly inserted record’s id.
-fn register_active_timer_db(conn: &Connection, duration_str: &str, message: &str) -> Result<i64> {
+/// Print the built-in alert sounds, marking the one used by default.
+fn list_sounds() {
+    let default = sounds::default_sound();
+    for sound in sounds::SOUNDS {
+        let marker = if sound.name == default.name { color("✓", "green") } else { " ".to_string() };
+        println!("  {} {:<8} {}", marker, color(sound.name, "purple"), color(sound.description, "gray"));
+    }
+    println!();
+    println!("  Preview with {}", color("tt sounds preview <name>", "cyan"));
+}
+
+/// Play a built-in sound once so it can be auditioned.
+fn preview_sound(name: &str) {
+    match sounds::find(name) {
+        Some(sound) => {
+            println!("  {} Playing {}...", color("🔔", "yellow"), color(sound.name, "purple"));
+            audio::preview_sound(sound);
+        }
+        None => {
+            eprintln!("Unknown sound '{}'. Available sounds: {}", name, sounds::names());
+            process::exit(1);
+        }
+    }
+}
+
+/// Inserts a new active timer record into active_timers, along with the timer_history entry
+/// it is counting down. Returns the newly inserted record’s id.
+fn register_active_timer_db(conn: &Connection, duration_str: &str, message: &str, labels: &Labels, history_id: Option<i64>) -> Result<i64> {
+    let pid = process::id() as i32;
+    let started = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO active_timers (pid, started, created, duration, message, project, flag_tags, history_id)
+         VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![pid, started, duration_str, message, labels.project, labels.flag_tags.join(" "), history_id],
+    )?;
+    let id = conn.last_insert_rowid();
+    set_active_timer_tags_db(conn, id, &labels.tags)?;
+    prompt::refresh_cache(conn);
+    Ok(id)
+}
+
+/// Starts a new countdown for an active timer after a snooze or restart, keeping its id and
+/// creation time. The row is recreated if the active view already removed it.
+#[allow(clippy::too_many_arguments)]
+fn reschedule_active_timer_db(conn: &Connection, active_id: i64, created: &str, duration_str: &str, message: &str, snoozes: u32, labels: &Labels, history_id: Option<i64>) -> Result<()> {
     let pid = process::id() as i32;
     let started = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     conn.execute(
-        "INSERT INTO active_timers (pid, started, duration, message) VALUES (?1, ?2, ?3, ?4)",
-        params![pid, started, duration_str, message],
+        "INSERT OR REPLACE INTO active_timers (id, pid, started, created, duration, message, snoozes, project, flag_tags, history_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![active_id, pid, started, created, duration_str, message, snoozes, labels.project, labels.flag_tags.join(" "), history_id],
     )?;
-    Ok(conn.last_insert_rowid())
+    set_active_timer_tags_db(conn, active_id, &labels.tags)?;
+    prompt::refresh_cache(conn);
+    Ok(())
 }
 
-/// Unregister an active timer by deleting it from the active_timers table, given its record id.
+/// Unregister an active timer by deleting it and its tag links, given its record id.
 fn unregister_active_timer_db(conn: &Connection, active_id: i64) -> Result<()> {
-    conn.execute("DELETE FROM active_timers WHERE id = ?1", params![active_id])?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM active_timers WHERE id = ?1", params![active_id])?;
+    tx.execute("DELETE FROM active_timer_tags WHERE active_id = ?1", params![active_id])?;
+    tx.commit()?;
+    prompt::refresh_cache(conn);
+    Ok(())
+}
+
+/// Parses a timestamp stored in the database ("%Y-%m-%d %H:%M:%S", local time).
+fn parse_db_time(s: &str) -> Option<chrono::DateTime<Local>> {
+    let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").ok()?;
+    Local.from_local_datetime(&naive).single()
+}
+
+/// Where an active timer's countdown is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerState {
+    Running,
+    Paused,
+    Ringing,
+}
+
+impl TimerState {
+    /// "running", "paused" or "ringing", as shown in lists and status bars.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimerState::Running => "running",
+            TimerState::Paused => "paused",
+            TimerState::Ringing => "ringing",
+        }
+    }
+}
+
+/// An active_timers row with its countdown worked out.
+#[derive(Clone)]
+pub struct ActiveTimer {
+    pub id: i64,
+    pub pid: i32,
+    pub duration: String,
+    pub message: String,
+    /// The countdown's full length, parsed from `duration`.
+    pub total: Duration,
+    /// Time left on the countdown; frozen while the timer is paused. Zero once it has gone off.
+    pub remaining: Duration,
+    pub paused: bool,
+    /// How long it has been ringing (or overdue) once the countdown is over.
+    pub overtime: Duration,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+}
+
+impl ActiveTimer {
+    /// The countdown is over and the alarm is waiting for an answer.
+    pub fn ringing(&self) -> bool {
+        !self.paused && self.remaining.is_zero()
+    }
+
+    /// Whether the timer is counting down, paused or ringing.
+    pub fn state(&self) -> TimerState {
+        if self.paused {
+            TimerState::Paused
+        } else if self.ringing() {
+            TimerState::Ringing
+        } else {
+            TimerState::Running
+        }
+    }
+
+    /// How much of the countdown has elapsed, 0-100.
+    pub fn percent(&self) -> u8 {
+        let total = self.total.as_secs_f64();
+        let elapsed = if total > 0.0 { 1.0 - self.remaining.as_secs_f64() / total } else { 1.0 };
+        (elapsed.clamp(0.0, 1.0) * 100.0).round() as u8
+    }
+}
+
+/// Countdown as "04:12" or "1:04:12", for lists, the tray and status bars.
+fn format_countdown(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// SQLite's data_version, which changes whenever another connection commits to the database.
+fn data_version(conn: &Connection) -> Option<i64> {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0)).ok()
+}
+
+/// Loads all active timers, ordered by id. Rows with an unreadable start time or duration are skipped.
+fn load_active_timers_db(conn: &Connection) -> Result<Vec<ActiveTimer>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, pid, started, duration, message, paused_at, fired_at, project,
+                (SELECT group_concat(name, ' ') FROM active_timer_tags JOIN tags ON tags.id = tag_id WHERE active_id = active_timers.id)
+         FROM active_timers ORDER BY id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i32>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<String>>(6)?,
+            row.get::<_, Option<String>>(7)?,
+            row.get::<_, Option<String>>(8)?,
+        ))
+    })?;
+    let mut timers = Vec::new();
+    for row in rows {
+        let (id, pid, started, duration, message, paused_at, fired_at, project, tags) = row?;
+        let (Some(started), Ok(dur)) = (parse_db_time(&started), parse_duration(&duration)) else { continue };
+        let paused_at = paused_at.as_deref().and_then(parse_db_time);
+        let end_time = started + chrono::Duration::from_std(dur).unwrap_or_default();
+        let remaining = (end_time - paused_at.unwrap_or_else(Local::now)).to_std().unwrap_or_default();
+        let fired_at = fired_at.as_deref().and_then(parse_db_time).unwrap_or(end_time);
+        let overtime = if paused_at.is_none() { (Local::now() - fired_at).to_std().unwrap_or_default() } else { Duration::ZERO };
+        timers.push(ActiveTimer {
+            id,
+            pid,
+            duration,
+            message: message.unwrap_or_default(),
+            total: dur,
+            remaining,
+            paused: paused_at.is_some(),
+            overtime,
+            tags: tags.map(|t| t.split(' ').map(str::to_string).collect()).unwrap_or_default(),
+            project,
+        });
+    }
+    Ok(timers)
+}
+
+/// Pauses an active timer's countdown. Returns false if it was not found or already paused.
+fn pause_active_timer_db(conn: &Connection, active_id: i64) -> Result<bool> {
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let changed = conn.execute(
+        "UPDATE active_timers SET paused_at = ?1 WHERE id = ?2 AND paused_at IS NULL",
+        params![now, active_id],
+    )?;
+    prompt::refresh_cache(conn);
+    Ok(changed > 0)
+}
+
+/// Resumes a paused timer by moving its start time forward by the time spent paused.
+/// Returns false if it was not found or not paused.
+fn resume_active_timer_db(conn: &Connection, active_id: i64) -> Result<bool> {
+    let row = conn.query_row(
+        "SELECT started, paused_at FROM active_timers WHERE id = ?1",
+        params![active_id],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+    );
+    let (started, paused_at) = match row {
+        Ok((started, Some(paused_at))) => (started, paused_at),
+        Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let (Some(started), Some(paused_at)) = (parse_db_time(&started), parse_db_time(&paused_at)) else { return Ok(false) };
+    let started = started + (Local::now() - paused_at);
+    conn.execute(
+        "UPDATE active_timers SET started = ?1, paused_at = NULL WHERE id = ?2",
+        params![started.format("%Y-%m-%d %H:%M:%S").to_string(), active_id],
+    )?;
+    prompt::refresh_cache(conn);
+    Ok(true)
+}
+
+/// When an active timer is due to go off according to the database, and whether it is paused.
+/// The timer process follows changes to this made from `tt tray` or `tt -a`.
+fn active_timer_schedule_db(conn: &Connection, active_id: i64) -> Option<(chrono::DateTime<Local>, bool)> {
+    let (started, duration, paused) = conn
+        .query_row(
+            "SELECT started, duration, paused_at IS NOT NULL FROM active_timers WHERE id = ?1",
+            params![active_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?)),
+        )
+        .ok()?;
+    let end = parse_db_time(&started)? + chrono::Duration::from_std(parse_duration(&duration).ok()?).ok()?;
+    Some((end, paused))
+}
+
+/// Restarts an active timer's countdown from now with its current duration (and unpauses it).
+fn restart_active_timer_db(conn: &Connection, active_id: i64) -> Result<bool> {
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let changed = conn.execute(
+        "UPDATE active_timers SET started = ?1, paused_at = NULL WHERE id = ?2",
+        params![now, active_id],
+    )?;
+    prompt::refresh_cache(conn);
+    Ok(changed > 0)
+}
+
+/// Changes an active timer's duration, e.g. to extend it by a minute.
+fn set_active_timer_duration_db(conn: &Connection, active_id: i64, duration: Duration) -> Result<bool> {
+    let duration_str = humantime::format_duration(duration).to_string();
+    let changed = conn.execute(
+        "UPDATE active_timers SET duration = ?1 WHERE id = ?2",
+        params![duration_str, active_id],
+    )?;
+    prompt::refresh_cache(conn);
+    Ok(changed > 0)
+}
+
+/// Marks an active timer as ringing.
+fn set_active_timer_fired_db(conn: &Connection, active_id: i64) -> Result<()> {
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "UPDATE active_timers SET fired_at = ?1, action = NULL WHERE id = ?2",
+        params![now, active_id],
+    )?;
+    prompt::refresh_cache(conn);
     Ok(())
 }
 
+/// Asks a ringing timer to snooze, restart or stop as if the popup had been answered. The timer
+/// process picks it up with `take_timer_action_db`.
+fn request_timer_action_db(conn: &Connection, active_id: i64, action: &TimerAction) -> Result<bool> {
+    let action = serde_json::to_string(action).expect("Failed to encode timer action");
+    let changed = conn.execute(
+        "UPDATE active_timers SET action = ?1 WHERE id = ?2 AND fired_at IS NOT NULL",
+        params![action, active_id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Takes a pending action requested from the terminal for a ringing timer, if any.
+fn take_timer_action_db(conn: &Connection, active_id: i64) -> Option<TimerAction> {
+    let action: String = conn
+        .query_row(
+            "SELECT action FROM active_timers WHERE id = ?1 AND action IS NOT NULL",
+            params![active_id],
+            |row| row.get(0),
+        )
+        .ok()?;
+    let _ = conn.execute("UPDATE active_timers SET action = NULL WHERE id = ?1", params![active_id]);
+    serde_json::from_str(&action).ok()
+}
+
+/// A timer_history entry whose alarm has been answered.
+pub struct FinishedTimer {
+    pub finished: chrono::DateTime<Local>,
+    pub duration: String,
+    pub message: String,
+    pub outcome: String,
+}
+
+/// Timers from timer_history that finished since `since`, newest first.
+fn recent_finished_db(conn: &Connection, since: chrono::DateTime<Local>, limit: usize) -> Result<Vec<FinishedTimer>> {
+    let mut stmt = conn.prepare(
+        "SELECT finished, duration, message, outcome FROM timer_history
+         WHERE finished >= ?1 ORDER BY finished DESC, id DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![since.format("%Y-%m-%d %H:%M:%S").to_string(), limit as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+        ))
+    })?;
+    let mut recent = Vec::new();
+    for row in rows {
+        let (finished, duration, message, outcome) = row?;
+        if let Some(finished) = parse_db_time(&finished) {
+            recent.push(FinishedTimer {
+                finished,
+                duration,
+                message: message.unwrap_or_default(),
+                outcome: outcome.unwrap_or_default(),
+            });
+        }
+    }
+    Ok(recent)
+}
+
+/// Changes the message shown when an active timer goes off, and relinks its tags from the
+/// new message's #tags plus the ones given with --tag.
+fn set_active_timer_message_db(conn: &Connection, active_id: i64, message: &str) -> Result<bool> {
+    let row = conn.query_row(
+        "SELECT project, coalesce(flag_tags, '') FROM active_timers WHERE id = ?1",
+        params![active_id],
+        |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?)),
+    );
+    let (project, flag_tags) = match row {
+        Ok(row) => row,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let flag_tags: Vec<String> = flag_tags.split_whitespace().map(str::to_string).collect();
+    let labels = Labels::new(&flag_tags, project.as_deref(), message);
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE active_timers SET message = ?1 WHERE id = ?2", params![message, active_id])?;
+    set_active_timer_tags_db(&tx, active_id, &labels.tags)?;
+    tx.commit()?;
+    prompt::refresh_cache(conn);
+    Ok(true)
+}
+
+/// The current message of an active timer, without the "(Snoozed) "/"(Restarted) " prefix.
+fn active_timer_message_db(conn: &Connection, active_id: i64) -> Option<String> {
+    let message: String = conn
+        .query_row("SELECT message FROM active_timers WHERE id = ?1", params![active_id], |row| row.get(0))
+        .ok()?;
+    let message = message.strip_prefix("(Snoozed) ").or_else(|| message.strip_prefix("(Restarted) ")).unwrap_or(&message);
+    Some(message.to_string())
+}
+
+/// Kills an active timer's process and removes its row. Returns false if there is no such timer.
+fn kill_active_timer_db(conn: &Connection, active_id: i64) -> Result<bool> {
+    let pid = conn.query_row(
+        "SELECT pid FROM active_timers WHERE id = ?1",
+        params![active_id],
+        |row| row.get::<_, i32>(0),
+    );
+    match pid {
+        Ok(pid) => {
+            kill_process(pid);
+            unregister_active_timer_db(conn, active_id)?;
+            Ok(true)
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
 /// Check for updates by comparing local version with GitHub releases
 fn check_for_updates() {
     use std::io::{self, Write};
@@ -439,8 +1349,6 @@ fn run_update() {
                 println!("  {} {}", color("✓", "green"), color("Update complete!", "green"));
                 println!();
                 
-                // Get the user's shell and exec into it to reload
-                let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
                 println!("  Run {} or open a new terminal to use the updated version.", color("exec $SHELL", "cyan"));
             }
             _ => {
@@ -537,22 +1445,11 @@ fn is_newer_version(latest: &str, current: &str) -> bool {
     false
 }
 
+/// Colors text with a palette name, 256-color code or hex value for the terminal theme.
+/// Unknown names fall back to bright white (15).
 fn color(text: &str, name: &str) -> String {
-    let code = match name.to_lowercase().as_str() {
-        "red"     => 210,
-        "green"   => 151,
-        "yellow"  => 229,
-        "blue"    => 153,
-        "magenta" => 219,
-        "cyan"    => 159,
-        "orange"  => 215,
-        "purple"  => 183,
-        "pink"    => 218,
-        "gray"    => 240,
-        "white"   => 255,
-        _ => 15,
-    };
-    format!("\x1B[38;5;{}m{}\x1B[0m", code, text)
+    let spec = theme::parse(name, theme::terminal_theme()).unwrap_or(theme::ColorSpec::Ansi(15));
+    format!("{}{}\x1B[0m", spec.ansi_fg(), text)
 }
 
 /// Gets a color name from env var, falling back to default
@@ -560,6 +1457,15 @@ fn get_env_color(env_var: &str, default: &str) -> String {
     std::env::var(env_var).unwrap_or_else(|_| default.to_string())
 }
 
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 /// Centers a string within a given width, accounting for unicode display width
 fn center_display_width(s: &str, width: usize) -> String {
     use unicode_width::UnicodeWidthStr;
@@ -574,17 +1480,6 @@ fn center_display_width(s: &str, width: usize) -> String {
     }
 }
 
-/// Pads a string to a specific display width, accounting for unicode/emoji widths
-fn pad_display_width(s: &str, width: usize) -> String {
-    use unicode_width::UnicodeWidthStr;
-    let current_width = UnicodeWidthStr::width(s);
-    if current_width >= width {
-        s.to_string()
-    } else {
-        format!("{}{}", s, " ".repeat(width - current_width))
-    }
-}
-
 /// Prints a fancy box for timer creation with Tokyo Night colors (Option D layout)
 /// Colors configurable via: TT_COLOR_HEADER, TT_COLOR_MESSAGE, TT_COLOR_TIME
 fn print_timer_started_box(duration: &str, message: &str, start_time: &str, end_time: &str, _is_background: bool) {
@@ -679,278 +1574,628 @@ fn print_timer_started_box(duration: &str, message: &str, start_time: &str, end_
     println!("  ╰{}╯", "─".repeat(inner_width));
 }
 
-/// Helper to color just the pipe characters gray
-fn replace_pipe_with_gray(line: &str) -> String {
-    let gray_pipe = color("│", "gray");
-    line.replace("│", &gray_pipe)
-}
-
-/// Signal handler for SIGINT to exit immediately.
-#[cfg(unix)]
-extern "C" fn handle_sigint(_sig: i32) {
-    process::exit(0);
-}
-
-/// Windows console control handler for Ctrl+C
-#[cfg(windows)]
-unsafe extern "system" fn handle_ctrl_c(ctrl_type: u32) -> i32 {
-    if ctrl_type == windows_sys::Win32::System::Console::CTRL_C_EVENT {
-        process::exit(0);
-    }
-    0
-}
-
-/// Displays a live view of active timers using the active_timers table.
-/// Rows are keyed by an autoincrement id.
-fn show_active_timer_db() -> Result<()> {
-    use std::io::{stdout, Write};
-    use crossterm::{
-        event::{self, Event, KeyCode, KeyEventKind},
-        terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
-        execute, cursor,
-    };
-    
-    // RAII guard to ensure terminal is always restored
-    struct TerminalGuard;
-    impl Drop for TerminalGuard {
-        fn drop(&mut self) {
-            let _ = crossterm::terminal::disable_raw_mode();
-            let _ = crossterm::execute!(std::io::stdout(), LeaveAlternateScreen);
-        }
-    }
-    
-    let conn = init_db()?;
-    let mut input_buffer = String::new();
-    let mut status_message: Option<(String, std::time::Instant)> = None;
-    
-    // Enter alternate screen and enable raw mode
-    execute!(stdout(), EnterAlternateScreen).ok();
-    terminal::enable_raw_mode().ok();
-    
-    // Guard ensures cleanup happens even on panic or early return
-    let _guard = TerminalGuard;
-    
-    loop {
-        // Clear and draw UI
-        let _ = execute!(stdout(), cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All));
-        
-        // Fixed column widths - message expands but capped for readability
-        let id_width = 4;       // "XX"
-        let dur_width = 5;      // "XXXm"
-        let time_width = 8;     // "HH:MM:SS"
-        let term_width = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
-        // Cap message width for readability, min 20, max 50
-        let message_width = term_width.saturating_sub(id_width + dur_width + time_width + 12).clamp(20, 50);
-        
-        // Print header
-        println!("{:>id_w$} | {:>dur_w$} | {:<msg_w$} | {:>time_w$}\r",
-            color("ID", "gray"),
-            color("Dur", "gray"),
-            color("Message", "gray"),
-            color("Left", "gray"),
-            id_w = id_width,
-            dur_w = dur_width,
-            msg_w = message_width,
-            time_w = time_width
-        );
-        let total_width = id_width + dur_width + message_width + time_width + 9;
-        println!("{}\r", "-".repeat(total_width));
-
-        // Query active timers from the DB.
-        let mut stmt = conn.prepare("SELECT id, pid, started, duration, message FROM active_timers ORDER BY id")?;
-        let active_iter = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, i32>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, String>(4)?,
-            ))
-        })?;
-        let mut active_timers = Vec::new();
-        for timer in active_iter {
-            active_timers.push(timer?);
-        }
-
-        // Display each active timer and compute remaining time.
-        for (id, _pid, started, duration_str, message) in active_timers.iter() {
-            if let Ok(start_time) = chrono::NaiveDateTime::parse_from_str(&started, "%Y-%m-%d %H:%M:%S") {
-                let start_time: chrono::DateTime<chrono::Local> =
-                    chrono::Local.from_local_datetime(&start_time).unwrap();
-                if let Ok(dur) = parse_duration(&duration_str) {
-                    let end_time = start_time + chrono::Duration::from_std(dur).unwrap();
-                    let now = chrono::Local::now();
-                    let time_left = end_time - now;
-                    if time_left.num_seconds() <= 0 {
-                        let _ = conn.execute("DELETE FROM active_timers WHERE id = ?1", params![id]);
-                        continue;
-                    }
-                    let secs = time_left.num_seconds();
-                    let hours = secs / 3600;
-                    let minutes = (secs % 3600) / 60;
-                    let seconds = secs % 60;
-                    let time_left_str = format!("{:02}:{:02}:{:02}", hours, minutes, seconds);
-                    
-                    // Wrap message using textwrap
-                    use textwrap::{fill, Options};
-                    let wrapped_message = fill(message, Options::new(message_width));
-                    let message_lines: Vec<&str> = wrapped_message.lines().collect();
-                    
-                    // First line with all columns
-                    println!(
-                        "{:<id_w$} | {:<dur_w$} | {:<msg_w$} | {}\r",
-                        color(&format!("{:>2}", id), "red"),
-                        color(&format!("{:>4}", duration_str), "pink"),
-                        color(message_lines.get(0).unwrap_or(&""), "purple"),
-                        color(&time_left_str, "green"),
-                        id_w = id_width,
-                        dur_w = dur_width,
-                        msg_w = message_width
-                    );
-                    
-                    // Continuation lines
-                    for line in message_lines.iter().skip(1) {
-                        println!(
-                            "{:<id_w$} | {:<dur_w$} | {:<msg_w$} |\r",
-                            "",
-                            "",
-                            color(line, "purple"),
-                            id_w = id_width,
-                            dur_w = dur_width,
-                            msg_w = message_width
-                        );
-                    }
-                }
-            }
-        }
-        
-        if active_timers.is_empty() {
-            println!("{}\r", color("No active timers", "gray"));
-        }
-        
-        println!("\r");
-        
-        // Show status message if recent
-        if let Some((msg, instant)) = &status_message {
-            if instant.elapsed() < std::time::Duration::from_secs(2) {
-                println!("{}\r", color(msg, "green"));
-            } else {
-                status_message = None;
-            }
-        }
-        
-        // Show input prompt with current buffer
-        println!("{}\r", color("Enter ID to kill, 'all', or Ctrl+C to exit:", "gray"));
-        print!("> {}", color(&input_buffer, "white"));
-        let _ = stdout().flush();
-
-        // Poll for input with 1 second timeout (for refresh)
-        if event::poll(std::time::Duration::from_secs(1)).unwrap_or(false) {
-            if let Ok(Event::Key(key_event)) = event::read() {
-                if key_event.kind == KeyEventKind::Press {
-                    match key_event.code {
-                        KeyCode::Char('c') if key_event.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                            return Ok(());
-                        }
-                        KeyCode::Enter => {
-                            let input = input_buffer.trim().to_string();
-                            input_buffer.clear();
-                            
-                            if !input.is_empty() {
-                                if input.eq_ignore_ascii_case("all") {
-                                    let mut stmt = conn.prepare("SELECT pid FROM active_timers")?;
-                                    let mut rows = stmt.query([])?;
-                                    while let Some(row) = rows.next()? {
-                                        let pid: i32 = row.get(0)?;
-                                        kill_process(pid);
-                                    }
-                                    conn.execute("DELETE FROM active_timers", [])?;
-                                    status_message = Some(("Killed all active timers.".to_string(), std::time::Instant::now()));
-                                } else if let Ok(active_id) = input.parse::<i64>() {
-                                    let mut stmt = conn.prepare("SELECT pid FROM active_timers WHERE id = ?1")?;
-                                    let mut rows = stmt.query(params![active_id])?;
-                                    if let Some(row) = rows.next()? {
-                                        let pid: i32 = row.get(0)?;
-                                        kill_process(pid);
-                                        let _ = conn.execute("DELETE FROM active_timers WHERE id = ?1", params![active_id]);
-                                        status_message = Some((format!("Killed timer {}", active_id), std::time::Instant::now()));
-                                    } else {
-                                        status_message = Some(("No active timer with that ID.".to_string(), std::time::Instant::now()));
-                                    }
-                                } else {
-                                    status_message = Some(("Invalid input.".to_string(), std::time::Instant::now()));
-                                }
-                            }
-                        }
-                        KeyCode::Backspace => {
-                            input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            input_buffer.push(c);
-                        }
-                        KeyCode::Esc => {
-                            return Ok(());
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
-    }
-}
-
 /// Audio support - available on macOS/Windows by default, or on Linux with "audio" feature
 #[cfg(any(not(target_os = "linux"), feature = "audio"))]
 mod audio {
+    use crate::sounds::Sound;
     use rodio::{Decoder, OutputStream, Sink, Source};
     use std::io::Cursor;
+    use std::time::Duration;
 
     /// Plays a looping sound and returns both the OutputStream and Sink.
-    pub fn play_sound_loop() -> (OutputStream, Sink) {
-        let audio_data: &[u8] = include_bytes!("../sounds/calm-loop-80576.mp3");
-        let cursor = Cursor::new(audio_data);
+    /// If `fade_in` is set, the volume ramps up from silent over that duration.
+    pub fn play_sound_loop(sound: &Sound, fade_in: Option<Duration>) -> (OutputStream, Sink) {
+        let cursor = Cursor::new(sound.data());
         let (stream, stream_handle) = OutputStream::try_default().expect("No audio output device");
         let sink = Sink::try_new(&stream_handle).expect("Failed to create sink");
         let source = Decoder::new(cursor).expect("Failed to decode").repeat_infinite();
-        sink.append(source);
+        match fade_in {
+            Some(fade) => sink.append(source.fade_in(fade)),
+            None => sink.append(source),
+        }
         (stream, sink)
     }
+
+    /// Plays a sound once (at most a few seconds of it) and waits for it to finish.
+    pub fn preview_sound(sound: &Sound) {
+        let (_stream, stream_handle) = OutputStream::try_default().expect("No audio output device");
+        let sink = Sink::try_new(&stream_handle).expect("Failed to create sink");
+        let source = Decoder::new(Cursor::new(sound.data()))
+            .expect("Failed to decode")
+            .take_duration(Duration::from_secs(5));
+        sink.append(source);
+        sink.sleep_until_end();
+    }
 }
 
-/// Fallback for Linux without audio feature - uses system bell
+/// Fallback for Linux without audio feature - loops the sound through a system player
+/// (paplay, pw-play, aplay or ffplay), or rings the system bell if none is installed.
 #[cfg(all(target_os = "linux", not(feature = "audio")))]
 mod audio {
+    use crate::sounds::Sound;
+    use std::path::PathBuf;
+    use std::process::{Child, Command, Stdio};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
     /// Dummy struct to match the API
     pub struct DummyStream;
-    pub struct DummySink;
-    
-    impl DummySink {
-        pub fn stop(&self) {}
+
+    /// Handle to the player loop; stopping or pausing it kills the running player.
+    #[derive(Clone, Default)]
+    pub struct PlayerSink {
+        stopped: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        child: Arc<Mutex<Option<Child>>>,
+    }
+
+    impl PlayerSink {
+        pub fn stop(&self) {
+            self.stopped.store(true, Ordering::SeqCst);
+            self.kill_player();
+        }
+
+        pub fn pause(&self) {
+            self.paused.store(true, Ordering::SeqCst);
+            self.kill_player();
+        }
+
+        pub fn play(&self) {
+            self.paused.store(false, Ordering::SeqCst);
+        }
+
+        fn kill_player(&self) {
+            if let Some(mut child) = self.child.lock().unwrap().take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+
+    /// System players in order of preference, with the arguments to play a file once.
+    /// aplay only understands WAV, so it is skipped for the MP3 loop.
+    const PLAYERS: &[(&str, &[&str], bool)] = &[
+        ("paplay", &[], true),
+        ("pw-play", &[], true),
+        ("aplay", &["-q"], false),
+        ("ffplay", &["-nodisp", "-autoexit", "-loglevel", "quiet"], true),
+    ];
+
+    /// Failed runs in a row after which a player that has worked before is given up on.
+    const MAX_PLAYER_FAILURES: u64 = 5;
+
+    fn find_in_path(program: &str) -> Option<PathBuf> {
+        let path = std::env::var_os("PATH")?;
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
     }
 
-    /// Plays a system bell as fallback (no audio library available)
-    pub fn play_sound_loop() -> (DummyStream, DummySink) {
+    /// Players that are installed and can handle the sound's format.
+    fn available_players(sound: &Sound) -> Vec<(PathBuf, &'static [&'static str])> {
+        PLAYERS
+            .iter()
+            .filter(|(_, _, plays_mp3)| sound.extension() == "wav" || *plays_mp3)
+            .filter_map(|(name, args, _)| find_in_path(name).map(|path| (path, *args)))
+            .collect()
+    }
+
+    /// Writes the sound to the cache directory (once) so external players can read it.
+    fn cached_sound_file(sound: &Sound) -> Option<PathBuf> {
+        let dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir)
+            .join("timer_cli");
+        let path = dir.join(format!("{}.{}", sound.name, sound.extension()));
+        let data = sound.data();
+        let up_to_date = std::fs::metadata(&path).map(|m| m.len() == data.len() as u64).unwrap_or(false);
+        if !up_to_date {
+            std::fs::create_dir_all(&dir).ok()?;
+            std::fs::write(&path, data).ok()?;
+        }
+        Some(path)
+    }
+
+    fn ring_bell() {
         // Print bell character to trigger system notification sound
         print!("\x07");
         let _ = std::io::Write::flush(&mut std::io::stdout());
-        (DummyStream, DummySink)
+    }
+
+    fn spawn_player(player: &(PathBuf, &[&str]), file: &PathBuf) -> Option<Child> {
+        Command::new(&player.0)
+            .args(player.1)
+            .arg(file)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()
+    }
+
+    /// Plays the sound on repeat through the first working system player until the sink is stopped.
+    /// External players have no volume ramp, so `fade_in` is ignored.
+    pub fn play_sound_loop(sound: &Sound, _fade_in: Option<Duration>) -> (DummyStream, PlayerSink) {
+        let sink = PlayerSink::default();
+        let players = available_players(sound);
+        let file = match cached_sound_file(sound) {
+            Some(file) if !players.is_empty() => file,
+            _ => {
+                ring_bell();
+                return (DummyStream, sink);
+            }
+        };
+
+        let handle = sink.clone();
+        std::thread::spawn(move || {
+            let mut players = players.into_iter();
+            let mut player = players.next();
+            let mut played_once = false;
+            let mut failures = 0;
+            while let Some(current) = &player {
+                while handle.paused.load(Ordering::SeqCst) && !handle.stopped.load(Ordering::SeqCst) {
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                if handle.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Some(child) = spawn_player(current, &file) else {
+                    player = players.next();
+                    continue;
+                };
+                *handle.child.lock().unwrap() = Some(child);
+                // Poll rather than wait so stop() can take the child and kill it. stop() or pause()
+                // may also have run just before the child was stored, so check the flags here too.
+                let success = loop {
+                    if handle.stopped.load(Ordering::SeqCst) || handle.paused.load(Ordering::SeqCst) {
+                        handle.kill_player();
+                        break true;
+                    }
+                    let mut guard = handle.child.lock().unwrap();
+                    match guard.as_mut().map(|c| c.try_wait()) {
+                        Some(Ok(Some(status))) => {
+                            guard.take();
+                            break status.success();
+                        }
+                        Some(Ok(None)) => {}
+                        _ => break true,
+                    }
+                    drop(guard);
+                    std::thread::sleep(Duration::from_millis(50));
+                };
+                // A player that fails on its first run (e.g. no sound server) is swapped for the next one.
+                if !success && !played_once && !handle.stopped.load(Ordering::SeqCst) {
+                    player = players.next();
+                    if player.is_none() {
+                        ring_bell();
+                    }
+                    continue;
+                }
+                // A player that worked before but keeps failing now (e.g. the sound server went
+                // away) is retried with a growing pause, then given up for the bell.
+                if !success && played_once {
+                    failures += 1;
+                    if failures >= MAX_PLAYER_FAILURES {
+                        ring_bell();
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_secs(failures));
+                    continue;
+                }
+                failures = 0;
+                played_once = true;
+            }
+        });
+        (DummyStream, sink)
+    }
+
+    /// Plays the sound once (at most a few seconds of it) through a system player.
+    pub fn preview_sound(sound: &Sound) {
+        let players = available_players(sound);
+        let file = if players.is_empty() { None } else { cached_sound_file(sound) };
+        if let Some(file) = file {
+            for player in &players {
+                let Some(mut child) = spawn_player(player, &file) else { continue };
+                let started = Instant::now();
+                let status = loop {
+                    match child.try_wait() {
+                        Ok(Some(status)) => break Some(status),
+                        Ok(None) if started.elapsed() < Duration::from_secs(5) => {
+                            std::thread::sleep(Duration::from_millis(50));
+                        }
+                        _ => {
+                            let _ = child.kill();
+                            let _ = child.wait();
+                            break None;
+                        }
+                    }
+                };
+                // Cut off after the preview length counts as played.
+                if status.is_none_or(|s| s.success()) {
+                    return;
+                }
+            }
+        }
+        eprintln!("No working audio player found (tried paplay, pw-play, aplay, ffplay); ringing the terminal bell.");
+        ring_bell();
+    }
+}
+
+/// Text-to-speech for `--say`, using the platform's built-in speech command:
+/// `say` on macOS, `spd-say` or `espeak` on Linux, and SAPI through PowerShell on Windows.
+mod speech {
+    use std::process::{Child, Command, Stdio};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    #[cfg(target_os = "macos")]
+    fn speak_command(text: &str) -> Option<Command> {
+        let mut cmd = Command::new("say");
+        cmd.arg(text);
+        Some(cmd)
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn speak_command(text: &str) -> Option<Command> {
+        // spd-say returns immediately unless told to wait, which would break the interleaving.
+        let candidates: [(&str, &[&str]); 3] = [("spd-say", &["--wait"]), ("espeak-ng", &[]), ("espeak", &[])];
+        let path = std::env::var_os("PATH")?;
+        candidates.iter().find_map(|(program, args)| {
+            std::env::split_paths(&path).find(|dir| dir.join(program).is_file())?;
+            let mut cmd = Command::new(program);
+            cmd.args(*args).arg(text);
+            Some(cmd)
+        })
+    }
+
+    #[cfg(windows)]
+    fn speak_command(text: &str) -> Option<Command> {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        // The text goes through an environment variable so it never has to be quoted for PowerShell.
+        let mut cmd = Command::new("powershell");
+        cmd.args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak($env:TT_SAY_TEXT)",
+        ])
+        .env("TT_SAY_TEXT", text)
+        .creation_flags(CREATE_NO_WINDOW);
+        Some(cmd)
+    }
+
+    /// Starts speaking `text`, or returns None if no speech command is available.
+    fn start(text: &str) -> Option<Child> {
+        speak_command(text)?
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()
+    }
+
+    /// Speaks `text` and waits for it to finish, giving up early if `cancel` is set.
+    /// Returns false if nothing could be spoken.
+    pub fn speak(text: &str, cancel: &AtomicBool) -> bool {
+        let Some(mut child) = start(text) else { return false };
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) | Err(_) => return true,
+                Ok(None) if cancel.load(Ordering::SeqCst) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return true;
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            }
+        }
+    }
+}
+
+/// Returns how often `--say` repeats the announcement while the alarm rings, from TT_SAY_INTERVAL.
+/// Defaults to every 30 seconds; "0" speaks only once.
+fn get_say_interval() -> Option<Duration> {
+    match std::env::var("TT_SAY_INTERVAL") {
+        Ok(s) => parse_duration(&s).ok().filter(|d| !d.is_zero()),
+        Err(_) => Some(Duration::from_secs(30)),
     }
 }
 
 /// Enum for the timer actions.
-#[derive(PartialEq)]
+///
+/// This is also the popup's reply to the timer process: one line of JSON on stdout,
+/// e.g. `{"action":"snooze","duration":"10m"}` or `{"action":"stop"}`.
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
 pub enum TimerAction {
-    Snooze,
+    /// Snooze for the chosen duration, as typed (e.g. "10m").
+    Snooze { duration: String },
     Restart,
     Stop,
+    /// Stop the timer and record a note about what got done on its history row.
+    Done { note: String },
+    /// The popup rang for TT_MAX_RING without an answer.
+    Timeout,
+}
+
+/// Everything the popup shows, sent by the timer process as JSON on the popup's stdin.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PopupRequest {
+    pub message: String,
+    /// The timer's original duration string, e.g. "25m".
+    pub duration: String,
+    /// Snooze used by the snooze key and main Snooze button.
+    pub snooze_default: String,
+    /// Snooze presets shown as buttons.
+    pub snooze_options: Vec<String>,
+    /// "dark", "light" or "system".
+    pub theme: String,
+    /// User color overrides (TT_COLOR_POPUP_*).
+    pub colors: theme::PopupColorOverrides,
+    /// Window position, size, always-on-top and fullscreen.
+    pub placement: PopupPlacement,
+    /// Re-raise the popup this often while unanswered (TT_REALERT_INTERVAL).
+    pub realert_interval_secs: Option<u64>,
+    /// Give up ringing after this long (TT_MAX_RING).
+    pub max_ring_secs: Option<u64>,
+    /// The timer's id in active_timers, as shown by `tt -a`.
+    pub timer_id: Option<i64>,
+    /// When the timer was first started (unix seconds).
+    pub started: i64,
+    /// When the timer went off (unix seconds); the popup counts overtime from here.
+    pub fired_at: i64,
+    /// How many times this timer has been snoozed so far.
+    pub snooze_count: u32,
+}
+
+impl PopupRequest {
+    /// Builds a request for a timer from the current configuration.
+    fn new(message: &str, duration: &str) -> Self {
+        let policy = get_escalation_policy();
+        PopupRequest {
+            message: message.to_string(),
+            duration: duration.to_string(),
+            snooze_default: get_snooze_duration_and_str().1,
+            snooze_options: get_snooze_presets().into_iter().map(|(_, s)| s).collect(),
+            theme: theme::configured(),
+            colors: theme::PopupColorOverrides::from_env(),
+            placement: get_popup_placement(),
+            realert_interval_secs: policy.realert_interval.map(|d| d.as_secs().max(1)),
+            max_ring_secs: policy.max_ring.map(|d| d.as_secs().max(1)),
+            fired_at: Local::now().timestamp(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Where the ringing timer came from, shown under the message in the popup.
+pub struct PopupContext {
+    pub timer_id: Option<i64>,
+    pub duration: String,
+    pub started: Option<chrono::DateTime<Local>>,
+    pub fired_at: chrono::DateTime<Local>,
+    pub snooze_count: u32,
+}
+
+impl PopupContext {
+    fn from_request(request: &PopupRequest) -> Self {
+        PopupContext {
+            timer_id: request.timer_id,
+            duration: request.duration.clone(),
+            started: Local.timestamp_opt(request.started, 0).single().filter(|_| request.started > 0),
+            fired_at: Local.timestamp_opt(request.fired_at, 0).single().unwrap_or_else(Local::now),
+            snooze_count: request.snooze_count,
+        }
+    }
+
+    /// Live "ringing for" counter, e.g. "01:05" or "1:02:05".
+    fn overtime(&self) -> String {
+        format_countdown((Local::now() - self.fired_at).to_std().unwrap_or_default())
+    }
 }
 
 /// Struct for the GUI popup.
 pub struct TimerPopup {
     pub sender: Option<std::sync::mpsc::Sender<TimerAction>>,
     pub message: String,
+    /// Timer details shown under the message.
+    pub context: PopupContext,
+    /// Snooze used by the snooze key and main Snooze button.
+    pub snooze_default: String,
+    /// "dark", "light" or "system" (follow the OS setting).
+    pub theme: String,
+    pub colors: theme::PopupColorOverrides,
+    /// Colors for the current theme, resolved at the start of each frame.
+    pub palette: theme::PopupPalette,
+    /// When the popup was first shown, used for the max ring time.
+    pub opened: std::time::Instant,
+    /// When the popup was last raised to the front.
+    pub last_raised: std::time::Instant,
+    /// Set while the popup is temporarily forced on top after a re-alert.
+    pub raised_on_top: bool,
+    /// Always on top for the whole time it is shown (TT_POPUP_ON_TOP or fullscreen).
+    pub on_top: bool,
+    /// Corner or center placement still to apply once the monitor size is known.
+    pub pending_position: Option<String>,
+    pub realert_interval: Option<Duration>,
+    pub max_ring: Option<Duration>,
+    /// Snooze durations offered as buttons, selectable with number keys 1-9.
+    pub snooze_presets: Vec<String>,
+    /// Contents of the custom snooze text field.
+    pub custom_snooze: String,
+    pub custom_snooze_error: Option<String>,
+    /// Set once Done is chosen: the note field replaces the Done button.
+    pub note: Option<String>,
+    /// Focus the note field on the next frame, right after it opens.
+    pub focus_note: bool,
+}
+
+impl TimerPopup {
+    /// Sends the chosen action back to the timer and closes the window.
+    fn finish(&mut self, action: TimerAction, frame: &mut eframe::Frame) {
+        if let Some(s) = self.sender.take() {
+            let _ = s.send(action);
+        }
+        frame.close();
+    }
+
+    /// Snoozes for the duration typed into the custom field, or shows why it can't be parsed.
+    fn submit_custom_snooze(&mut self, frame: &mut eframe::Frame) {
+        let text = self.custom_snooze.trim().to_string();
+        match parse_duration(&text) {
+            Ok(dur) if !dur.is_zero() => self.finish(TimerAction::Snooze { duration: text }, frame),
+            _ => self.custom_snooze_error = Some(format!("Can't parse \"{}\" (try 20m or 1h 30m)", text)),
+        }
+    }
+
+    /// Original duration and start time, live overtime, snooze count and timer id.
+    fn show_context(&self, ui: &mut egui::Ui) {
+        let context = &self.context;
+        let mut first_line = format!("⏱ {}", context.duration);
+        if let Some(started) = context.started {
+            first_line.push_str(&format!("  ·  started {}", started.format("%-I:%M %p")));
+        }
+        ui.colored_label(rgb(self.palette.muted), first_line);
+
+        let mut second_line = format!("🔔 ringing for {}", context.overtime());
+        if context.snooze_count > 0 {
+            let times = if context.snooze_count == 1 { "once".to_string() } else { format!("{}×", context.snooze_count) };
+            second_line.push_str(&format!("  ·  snoozed {}", times));
+        }
+        if let Some(id) = context.timer_id {
+            second_line.push_str(&format!("  ·  #{}", id));
+        }
+        ui.colored_label(rgb(self.palette.muted), second_line);
+    }
+
+    /// Row of preset snooze buttons ("[1] 1m", ...) followed by a custom duration field.
+    fn show_snooze_picker(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
+        let preset_width = 64.0;
+        let spacing = ui.spacing().item_spacing.x;
+        let count = self.snooze_presets.len() as f32;
+        let row_width = count * preset_width + (count - 1.0).max(0.0) * spacing;
+        let mut chosen = None;
+        ui.horizontal(|ui| {
+            ui.add_space(((ui.available_width() - row_width) / 2.0).max(0.0));
+            for (i, label) in self.snooze_presets.iter().enumerate() {
+                let text = styled_button_label(&format!("[{}] ", i + 1), rgb(self.palette.snooze), label, rgb(self.palette.text));
+                if ui.add_sized(egui::vec2(preset_width, 28.0), egui::Button::new(text)).clicked() {
+                    chosen = Some(label.clone());
+                }
+            }
+        });
+        if let Some(duration) = chosen {
+            self.finish(TimerAction::Snooze { duration }, frame);
+        }
+
+        ui.add_space(6.0);
+        let field_width = 110.0;
+        let button_width = 70.0;
+        let mut submit = false;
+        ui.horizontal(|ui| {
+            ui.add_space(((ui.available_width() - field_width - button_width - spacing) / 2.0).max(0.0));
+            let response = ui.add_sized(
+                egui::vec2(field_width, 28.0),
+                egui::TextEdit::singleline(&mut self.custom_snooze).hint_text("custom, e.g. 20m"),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                submit = true;
+            }
+            if ui.add_sized(egui::vec2(button_width, 28.0), egui::Button::new("Snooze")).clicked() {
+                submit = true;
+            }
+        });
+        if submit {
+            self.submit_custom_snooze(frame);
+        }
+        if let Some(error) = &self.custom_snooze_error {
+            ui.colored_label(rgb(self.palette.error), error);
+        }
+    }
+
+    /// The Done button, which opens a note field; Enter or Save stops the timer with the note.
+    fn show_done(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame, key_char: char) {
+        let Some(note) = self.note.as_mut() else {
+            let label = styled_button_label(&format!("[ {} ] ", key_char), rgb(self.palette.message), "Done + note", rgb(self.palette.text));
+            if ui.add_sized(egui::vec2(150.0, 40.0), egui::Button::new(label)).clicked() {
+                self.note = Some(String::new());
+                self.focus_note = true;
+            }
+            return;
+        };
+
+        let field_width = 220.0;
+        let button_width = 70.0;
+        let spacing = ui.spacing().item_spacing.x;
+        let mut submit = false;
+        ui.horizontal(|ui| {
+            ui.add_space(((ui.available_width() - field_width - button_width - spacing) / 2.0).max(0.0));
+            let response = ui.add_sized(
+                egui::vec2(field_width, 28.0),
+                egui::TextEdit::singleline(note).hint_text("What did you get done?"),
+            );
+            if self.focus_note {
+                response.request_focus();
+                self.focus_note = false;
+            }
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                submit = true;
+            }
+            if ui.add_sized(egui::vec2(button_width, 28.0), egui::Button::new("Save")).clicked() {
+                submit = true;
+            }
+        });
+        if submit {
+            let note = self.note.take().unwrap_or_default().trim().to_string();
+            self.finish(TimerAction::Done { note }, frame);
+        }
+    }
+}
+
+/// Moves the window to the center or a corner of the monitor. eframe 0.21 only reports the size
+/// of the monitor the window opened on, not where that monitor is or which one is primary, so
+/// corners are that size measured from the screen origin. They land correctly when the window
+/// opens on the monitor at the origin (usually the primary one). Returns false while the monitor
+/// size is not known yet.
+fn place_window(frame: &mut eframe::Frame, position: &str) -> bool {
+    let info = frame.info().window_info;
+    let (Some(monitor), size) = (info.monitor_size, info.size) else { return false };
+    let margin = 20.0;
+    let right = (monitor.x - size.x - margin).max(0.0);
+    let bottom = (monitor.y - size.y - margin).max(0.0);
+    let pos = match position {
+        "top-left" => egui::pos2(margin, margin),
+        "top-right" => egui::pos2(right, margin),
+        "bottom-left" => egui::pos2(margin, bottom),
+        "bottom-right" => egui::pos2(right, bottom),
+        _ => {
+            frame.set_centered();
+            return true;
+        }
+    };
+    frame.set_window_pos(pos);
+    true
+}
+
+fn rgb([r, g, b]: [u8; 3]) -> Color32 {
+    Color32::from_rgb(r, g, b)
 }
 
+const NUMBER_KEYS: [egui::Key; 9] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+    egui::Key::Num7,
+    egui::Key::Num8,
+    egui::Key::Num9,
+];
+
 /// Implement the eframe App for TimerPopup with custom styling.
 /// This version centers the window and buttons and auto-sizes to its content.
 /// The window title is set to an empty string so that the custom message is shown at the top.
@@ -964,25 +2209,67 @@ impl App for TimerPopup {
     }
     
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Escalation: give up after the max ring time, and periodically bring the popup back to
+        // the front in case it got lost behind other windows.
+        if self.max_ring.is_some_and(|max| self.opened.elapsed() >= max) {
+            self.finish(TimerAction::Timeout, frame);
+        }
+        if self.pending_position.as_deref().is_some_and(|position| place_window(frame, position)) {
+            self.pending_position = None;
+        }
+        if self.raised_on_top && self.last_raised.elapsed() >= Duration::from_secs(1) {
+            frame.set_always_on_top(self.on_top);
+            self.raised_on_top = false;
+        }
+        if let Some(interval) = self.realert_interval {
+            if self.last_raised.elapsed() >= interval {
+                frame.set_minimized(false);
+                frame.set_visible(true);
+                frame.set_always_on_top(true);
+                self.raised_on_top = true;
+                self.last_raised = std::time::Instant::now();
+            }
+        }
+        // Repaint every second for the overtime counter and escalation checks.
+        ctx.request_repaint_after(Duration::from_secs(1));
+        let theme = match self.theme.as_str() {
+            "light" => theme::Theme::Light,
+            "system" if frame.info().system_theme == Some(eframe::Theme::Light) => theme::Theme::Light,
+            _ => theme::Theme::Dark,
+        };
+        self.palette = theme::PopupPalette::new(theme, &self.colors);
+        ctx.set_visuals(match theme {
+            theme::Theme::Light => egui::Visuals::light(),
+            theme::Theme::Dark => egui::Visuals::dark(),
+        });
+
         let key_snooze = get_action_key("TT_KEY_SNOOZE", egui::Key::Z);
         let key_restart = get_action_key("TT_KEY_RESTART", egui::Key::R);
         let key_stop = get_action_key("TT_KEY_STOP", egui::Key::S);
+        let key_done = get_action_key("TT_KEY_DONE", egui::Key::D);
         
-        if ctx.input(|i| i.key_pressed(key_snooze)) {
-            if let Some(s) = self.sender.take() {
-                let _ = s.send(TimerAction::Snooze);
-            }
-            frame.close();
+        // Shortcuts are ignored while typing a custom snooze duration.
+        let typing = ctx.memory(|m| m.focus().is_some());
+        let preset_pressed = NUMBER_KEYS
+            .iter()
+            .take(self.snooze_presets.len())
+            .position(|key| ctx.input(|i| i.key_pressed(*key)));
+
+        if typing {
+            // Handled by the text field below.
+        } else if ctx.input(|i| i.key_pressed(key_snooze)) {
+            let duration = self.snooze_default.clone();
+            self.finish(TimerAction::Snooze { duration }, frame);
+        } else if let Some(index) = preset_pressed {
+            let duration = self.snooze_presets[index].clone();
+            self.finish(TimerAction::Snooze { duration }, frame);
         } else if ctx.input(|i| i.key_pressed(key_restart)) {
-            if let Some(s) = self.sender.take() {
-                let _ = s.send(TimerAction::Restart);
-            }
-            frame.close();
+            self.finish(TimerAction::Restart, frame);
+        } else if ctx.input(|i| i.key_pressed(key_done)) && self.note.is_none() {
+            self.note = Some(String::new());
+            self.focus_note = true;
         } else if ctx.input(|i| i.key_pressed(key_stop) || i.key_pressed(egui::Key::Escape)) {
-            if let Some(s) = self.sender.take() {
-                let _ = s.send(TimerAction::Stop);
-            }
-            frame.close();
+            self.finish(TimerAction::Stop, frame);
         }
 
     egui::Window::new("Time's Up!")
@@ -994,63 +2281,109 @@ impl App for TimerPopup {
             ui.set_min_width(ui.available_width());
 
             ui.vertical_centered(|ui| {
-                if self.message.len() > 0 {
+                if !self.message.is_empty() {
                     ui.add_space(25.0);
                 }
-                ui.colored_label(egui::Color32::LIGHT_GREEN, format!("\"{}\"", &self.message));
+                ui.colored_label(rgb(self.palette.message), format!("\"{}\"", &self.message));
+                ui.add_space(12.0);
+                self.show_context(ui);
                 ui.add_space(20.0);
 
-                let (_snooze_duration, snooze_str) = get_snooze_duration_and_str();
+                let snooze_str = self.snooze_default.clone();
                 let key_snooze_char = get_action_key_char("TT_KEY_SNOOZE", 'z');
                 let key_restart_char = get_action_key_char("TT_KEY_RESTART", 'r');
                 let key_stop_char = get_action_key_char("TT_KEY_STOP", 's');
+                let key_done_char = get_action_key_char("TT_KEY_DONE", 'd');
                 
                 let buttons = [
                     (
-                        styled_button_label(&format!("[ {} ] ", key_snooze_char), Color32::from_rgb(128, 128, 255), &format!("Snooze ({})", snooze_str)),
-                        TimerAction::Snooze,
+                        styled_button_label(&format!("[ {} ] ", key_snooze_char), rgb(self.palette.snooze), &format!("Snooze ({})", snooze_str), rgb(self.palette.text)),
+                        TimerAction::Snooze { duration: snooze_str.clone() },
                     ),
                     (
-                        styled_button_label(&format!("[ {} ] ", key_restart_char), Color32::from_rgb(0, 255, 128), "Restart"),
+                        styled_button_label(&format!("[ {} ] ", key_restart_char), rgb(self.palette.restart), "Restart", rgb(self.palette.text)),
                         TimerAction::Restart,
                     ),
                     (
-                        styled_button_label(&format!("[ {} ] ", key_stop_char), Color32::from_rgb(255, 0, 0), "Stop"),
+                        styled_button_label(&format!("[ {} ] ", key_stop_char), rgb(self.palette.stop), "Stop", rgb(self.palette.text)),
                         TimerAction::Stop,
                     ),
                 ];
 
-                for (label, action) in buttons {
+                for (i, (label, action)) in buttons.into_iter().enumerate() {
                     if ui.add_sized(egui::vec2(150.0, 40.0), egui::Button::new(label)).clicked() {
-                        if let Some(s) = self.sender.take() {
-                            let _ = s.send(action);
-                        }
-                        frame.close();
+                        self.finish(action, frame);
+                    }
+                    ui.add_space(if i == 0 { 10.0 } else { 25.0 });
+
+                    // Snooze presets and a custom duration field sit right under the Snooze button.
+                    if i == 0 {
+                        self.show_snooze_picker(ui, frame);
+                        ui.add_space(25.0);
+                    }
+                    // Done + note sits between Restart and Stop.
+                    if i == 1 {
+                        self.show_done(ui, frame, key_done_char);
+                        ui.add_space(25.0);
                     }
-                    ui.add_space(25.0);
                 }
             });
         });
     }
 }
 
-/// In popup mode, run the GUI popup and print the action to stdout.
+/// In popup mode, read the PopupRequest from stdin, run the GUI popup and print the
+/// chosen TimerAction to stdout as JSON.
 fn run_popup() {
-    let args: Vec<String> = std::env::args().collect();
-    let message = if let Some(pos) = args.iter().position(|a| a == "--message") {
-        if pos + 1 < args.len() {
-            args[pos + 1].clone()
-        } else {
-            "".to_string()
-        }
+    let mut input = String::new();
+    let _ = std::io::stdin().read_to_string(&mut input);
+    let request: PopupRequest = serde_json::from_str(&input).unwrap_or_default();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let now = std::time::Instant::now();
+    let context = PopupContext::from_request(&request);
+    let placement = request.placement.clone();
+    let explicit_pos = parse_pair(&placement.position, ',');
+    let snooze_default = if parse_duration(&request.snooze_default).is_ok() {
+        request.snooze_default
     } else {
-        "".to_string()
+        get_snooze_duration_and_str().1
     };
-    let (tx, rx) = std::sync::mpsc::channel();
-    let app = TimerPopup { sender: Some(tx), message };
+    let app = TimerPopup {
+        sender: Some(tx),
+        context,
+        message: request.message,
+        snooze_default,
+        palette: theme::PopupPalette::new(theme::Theme::Dark, &request.colors),
+        theme: request.theme.to_lowercase(),
+        colors: request.colors,
+        opened: now,
+        last_raised: now,
+        raised_on_top: false,
+        on_top: placement.on_top || placement.fullscreen,
+        // Explicit "X,Y" coordinates go straight into the native options below.
+        pending_position: (!placement.fullscreen && explicit_pos.is_none()).then(|| placement.position.clone()),
+        realert_interval: request.realert_interval_secs.map(Duration::from_secs),
+        max_ring: request.max_ring_secs.map(Duration::from_secs),
+        snooze_presets: request
+            .snooze_options
+            .into_iter()
+            .filter(|s| parse_duration(s).is_ok())
+            .take(NUMBER_KEYS.len())
+            .collect(),
+        custom_snooze: String::new(),
+        custom_snooze_error: None,
+        note: None,
+        focus_note: false,
+    };
+    let (width, height) = placement.size.unwrap_or(POPUP_SIZE);
     let native_options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(400.0, 350.0)),
+        initial_window_size: Some(egui::vec2(width, height)),
+        initial_window_pos: explicit_pos.map(|(x, y)| egui::pos2(x, y)),
+        always_on_top: placement.on_top || placement.fullscreen,
+        fullscreen: placement.fullscreen,
         resizable: true,
+        follow_system_theme: true,
         ..Default::default()
     };
     // Use a fixed window title "Terminal Timer"
@@ -1059,21 +2392,23 @@ fn run_popup() {
         Box::new(move |_cc| Box::new(app))
     );
     let action = rx.recv().unwrap_or(TimerAction::Stop);
-    match action {
-        TimerAction::Snooze => println!("snooze"),
-        TimerAction::Restart => println!("restart"),
-        TimerAction::Stop => println!("stop"),
-    }
+    println!("{}", serde_json::to_string(&action).expect("Failed to encode popup response"));
 }
 
 /// Spawns a separate process to show the popup and returns the chosen action.
-/// This sets the environment variable "POPUP_MODE" so the child runs popup mode.
-fn spawn_popup(popup_message: &str) -> TimerAction {
+/// This sets the environment variable "POPUP_MODE" so the child runs popup mode, writes the
+/// request to its stdin and reads the JSON response from its stdout. Anything unreadable
+/// (e.g. the popup could not open a window) counts as Stop. While it is open, `remote_action`
+/// is polled for an answer given from the terminal instead, which closes the popup.
+fn spawn_popup(request: &PopupRequest, mut remote_action: impl FnMut() -> Option<TimerAction>) -> TimerAction {
+    use std::process::Stdio;
+
     let current_exe = std::env::current_exe().expect("Failed to get current executable");
     let mut cmd = Command::new(current_exe);
     cmd.env("POPUP_MODE", "1")
-        .arg("--message")
-        .arg(popup_message);
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
     
     #[cfg(windows)]
     {
@@ -1082,72 +2417,246 @@ fn spawn_popup(popup_message: &str) -> TimerAction {
         cmd.creation_flags(CREATE_NO_WINDOW);
     }
     
-    let output = cmd.output().expect("Failed to spawn popup process");
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    match stdout.trim() {
-        "snooze" => TimerAction::Snooze,
-        "restart" => TimerAction::Restart,
-        _ => TimerAction::Stop,
+    let mut child = cmd.spawn().expect("Failed to spawn popup process");
+    if let Some(mut stdin) = child.stdin.take() {
+        let request = serde_json::to_string(request).expect("Failed to encode popup request");
+        let _ = stdin.write_all(request.as_bytes());
+        // Dropping stdin closes it so the popup sees the end of the request.
+    }
+    // Read the reply on a thread so an action requested from the terminal (`tt -a`) can
+    // close the popup in the meantime.
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut stdout = child.stdout.take();
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        let _ = tx.send(output);
+    });
+    let output = loop {
+        match rx.recv_timeout(Duration::from_millis(500)) {
+            Ok(output) => break output,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                if let Some(action) = remote_action() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return action;
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break String::new(),
+        }
+    };
+    let _ = child.wait();
+    output
+        .lines()
+        .rev()
+        .find_map(|line| serde_json::from_str(line).ok())
+        .unwrap_or(TimerAction::Stop)
+}
+
+/// How a timer announces itself when time is up.
+struct AlarmOptions {
+    sound: &'static sounds::Sound,
+    /// Whether --sound was given. Otherwise the history doesn't keep the sound, so `tt again`
+    /// plays whatever TT_SOUND picks by then.
+    sound_given: bool,
+    /// Speak the message between repetitions of the sound (`--say`).
+    say: bool,
+    /// Fullscreen, always-on-top popup (`--block`).
+    block: bool,
+}
+
+/// The sink returned by `audio::play_sound_loop` on this platform.
+#[cfg(any(not(target_os = "linux"), feature = "audio"))]
+type AlarmSink = rodio::Sink;
+#[cfg(all(target_os = "linux", not(feature = "audio")))]
+type AlarmSink = audio::PlayerSink;
+
+/// Speaks `text` while the alarm rings, pausing the sound for each announcement and
+/// repeating every TT_SAY_INTERVAL until `done` is set.
+fn announce_loop(text: String, sink: std::sync::Arc<AlarmSink>, done: std::sync::Arc<std::sync::atomic::AtomicBool>) {
+    use std::sync::atomic::Ordering;
+    let interval = get_say_interval();
+    loop {
+        sink.pause();
+        let spoke = speech::speak(&text, &done);
+        if !done.load(Ordering::SeqCst) {
+            sink.play();
+        }
+        if !spoke {
+            eprintln!("--say: no speech command found (say, spd-say, espeak).");
+            return;
+        }
+        let Some(interval) = interval else { return };
+        let started = std::time::Instant::now();
+        while started.elapsed() < interval {
+            if done.load(Ordering::SeqCst) {
+                return;
+            }
+            sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+/// Waits out a countdown. Pauses, restarts and extensions made from `tt tray` or `tt -a` are
+/// picked up by watching the timer's row in active_timers: any change to its due time moves the
+/// local deadline by the same amount. The row is only read again when `data_version` shows
+/// another process wrote to the database. With `show_progress`, draws the foreground countdown.
+fn wait_for_timer(conn: &Connection, active_id: i64, duration: Duration, show_progress: bool) {
+    use std::time::Instant;
+
+    let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    let update_interval = if show_progress { Duration::from_millis(100) } else { Duration::from_secs(1) };
+    let mut deadline = Instant::now() + duration;
+    let mut version = data_version(conn);
+    let mut schedule = active_timer_schedule_db(conn, active_id);
+    let mut scheduled_end = schedule.map(|(end, _)| end);
+    let mut paused_since: Option<Instant> = None;
+    let mut tick = 0usize;
+    loop {
+        let current_version = data_version(conn);
+        if current_version != version {
+            version = current_version;
+            schedule = active_timer_schedule_db(conn, active_id);
+            if let (Some(old_end), Some((new_end, _))) = (scheduled_end, schedule) {
+                let shift = new_end - old_end;
+                match shift.to_std() {
+                    Ok(later) => deadline += later,
+                    Err(_) => deadline = deadline.checked_sub((-shift).to_std().unwrap_or_default()).unwrap_or_else(Instant::now),
+                }
+            }
+            if schedule.is_some() {
+                scheduled_end = schedule.map(|(end, _)| end);
+            }
+        }
+        match (schedule.is_some_and(|(_, paused)| paused), paused_since) {
+            (true, None) => paused_since = Some(Instant::now()),
+            (false, Some(_)) => paused_since = None,
+            _ => {}
+        }
+        let remaining = deadline.saturating_duration_since(paused_since.unwrap_or_else(Instant::now));
+        if show_progress {
+            // Round up so the display reaches 00:00:00 exactly when the timer fires.
+            let seconds_remaining = (remaining.as_millis() as u64).div_ceil(1000);
+            let hours = seconds_remaining / 3600;
+            let minutes = (seconds_remaining % 3600) / 60;
+            let seconds = seconds_remaining % 60;
+            if paused_since.is_some() {
+                print!("\r\x1B[33mTime remaining: {:02}:{:02}:{:02} ⏸ paused\x1B[0m", hours, minutes, seconds);
+            } else {
+                let spinner = spinner_chars[tick % spinner_chars.len()];
+                print!("\r\x1B[32mTime remaining: {:02}:{:02}:{:02} {}        \x1B[0m", hours, minutes, seconds, spinner);
+            }
+            std::io::stdout().flush().unwrap();
+            tick += 1;
+        }
+        if paused_since.is_none() && remaining.is_zero() {
+            break;
+        }
+        let wait = if paused_since.is_some() { update_interval } else { update_interval.min(remaining) };
+        sleep(wait);
+    }
+    if show_progress {
+        println!();
     }
 }
 
 /// Runs the timer. When time's up, it plays the sound and spawns a separate popup process.
-/// Depending on the chosen action, it deletes the old active timer record and inserts a new one.
+/// Depending on the chosen action, it reschedules the active timer record (keeping its id) or removes it.
 /// Durations are stored using the original formatting string.
-fn run_timer(mut duration: Duration, original_duration_str: String, popup_message: String, show_progress: bool) {
+/// `history_id` is the timer_history row of the current run; each ending is recorded on it as an outcome.
+fn run_timer(mut duration: Duration, original_duration_str: String, mut popup_message: String, show_progress: bool, mut history_id: Option<i64>, alarm: &AlarmOptions, labels: &Labels) {
     let conn = init_db().expect("Failed to initialize DB");
+    let mut labels = labels.clone();
+    let policy = get_escalation_policy();
     // Insert the initial active timer record using the original duration string.
-    let mut active_timer_id = register_active_timer_db(&conn, &original_duration_str, &popup_message)
+    let active_timer_id = register_active_timer_db(&conn, &original_duration_str, &popup_message, &labels, history_id)
         .expect("Failed to register active timer");
+    let created = Local::now();
+    let created_str = created.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut snooze_count: u32 = 0;
+    // Snoozes and restarts are logged as entries of this timer's first one.
+    let run_id = history_id;
 
     loop {
-        if show_progress {
-            let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-            let total_millis = duration.as_secs() * 1000;
-            let update_interval = 100;
-            let total_ticks = total_millis / update_interval;
-            for tick in (0..=total_ticks).rev() {
-                let remaining_millis = tick * update_interval;
-                let seconds_remaining = remaining_millis / 1000;
-                let hours = seconds_remaining / 3600;
-                let minutes = (seconds_remaining % 3600) / 60;
-                let seconds = seconds_remaining % 60;
-                let spinner = spinner_chars[(tick as usize) % spinner_chars.len()];
-                print!("\r\x1B[32mTime remaining: {:02}:{:02}:{:02} {} \x1B[0m", hours, minutes, seconds, spinner);
-                std::io::stdout().flush().unwrap();
-                sleep(Duration::from_millis(update_interval));
-            }
-            println!();
-        } else {
-            sleep(duration);
+        wait_for_timer(&conn, active_timer_id, duration, show_progress);
+        // The message may have been edited in `tt -a` while counting down.
+        if let Some(message) = active_timer_message_db(&conn, active_timer_id).filter(|m| *m != popup_message) {
+            labels = Labels::new(&labels.flag_tags, labels.project.as_deref(), &message);
+            popup_message = message;
         }
         println!("Time's up!");
-        let (_stream, sink) = audio::play_sound_loop();
-        let action = spawn_popup(&popup_message);
+        let _ = set_active_timer_fired_db(&conn, active_timer_id);
+        let (_stream, sink) = audio::play_sound_loop(alarm.sound, policy.fade_in);
+        let sink = std::sync::Arc::new(sink);
+        let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let announcer = alarm.say.then(|| {
+            let text = if popup_message.is_empty() { "Time's up".to_string() } else { popup_message.clone() };
+            let (sink, done) = (sink.clone(), done.clone());
+            std::thread::spawn(move || announce_loop(text, sink, done))
+        });
+        let mut request = PopupRequest {
+            timer_id: Some(active_timer_id),
+            started: created.timestamp(),
+            fired_at: Local::now().timestamp(),
+            snooze_count,
+            ..PopupRequest::new(&popup_message, &original_duration_str)
+        };
+        request.placement.fullscreen |= alarm.block;
+        let action = spawn_popup(&request, || take_timer_action_db(&conn, active_timer_id));
+        done.store(true, std::sync::atomic::Ordering::SeqCst);
         sink.stop();
+        if let Some(announcer) = announcer {
+            let _ = announcer.join();
+        }
+
+        // An unanswered popup falls back to the configured max ring action.
+        let (action, outcome) = match action {
+            TimerAction::Snooze { duration } => (TimerAction::Snooze { duration }, "snoozed"),
+            TimerAction::Restart => (TimerAction::Restart, "restarted"),
+            TimerAction::Stop => (TimerAction::Stop, "stopped"),
+            TimerAction::Done { note } => {
+                if let Some(id) = history_id {
+                    let _ = set_timer_note_db(&conn, id, note.trim());
+                }
+                (TimerAction::Done { note }, "done")
+            }
+            TimerAction::Timeout if policy.snooze_on_max_ring => {
+                (TimerAction::Snooze { duration: get_snooze_duration_and_str().1 }, "auto-snoozed")
+            }
+            TimerAction::Timeout => (TimerAction::Stop, "auto-stopped"),
+        };
+        if let Some(id) = history_id {
+            let _ = set_timer_outcome_db(&conn, id, outcome);
+        }
+
         match action {
-            TimerAction::Snooze => {
-                let (snooze_duration, snooze_str) = get_snooze_duration_and_str();
+            TimerAction::Snooze { duration: snooze_str } => {
+                // The popup only sends durations it could parse; fall back to the default just in case.
+                let (snooze_duration, snooze_str) = match parse_duration(&snooze_str) {
+                    Ok(dur) => (dur, snooze_str),
+                    Err(_) => get_snooze_duration_and_str(),
+                };
                 let new_message = format!("(Snoozed) {}", popup_message);
-                unregister_active_timer_db(&conn, active_timer_id).unwrap();
-                active_timer_id = register_active_timer_db(&conn, &snooze_str, &new_message)
+                snooze_count += 1;
+                history_id = log_timer_creation_db(&conn, &snooze_str, &new_message, false, &labels, alarm, run_id).ok();
+                reschedule_active_timer_db(&conn, active_timer_id, &created_str, &snooze_str, &new_message, snooze_count, &labels, history_id)
                     .expect("Failed to register snoozed timer");
-                log_timer_creation_db(&conn, &snooze_str, &new_message, false).unwrap();
                 println!("Snoozing for {}...", snooze_str);
                 duration = snooze_duration;
                 continue;
             },
             TimerAction::Restart => {
                 let new_message = format!("(Restarted) {}", popup_message);
-                unregister_active_timer_db(&conn, active_timer_id).unwrap();
-                active_timer_id = register_active_timer_db(&conn, &original_duration_str, &new_message)
+                history_id = log_timer_creation_db(&conn, &original_duration_str, &new_message, false, &labels, alarm, run_id).ok();
+                reschedule_active_timer_db(&conn, active_timer_id, &created_str, &original_duration_str, &new_message, snooze_count, &labels, history_id)
                     .expect("Failed to register restarted timer");
-                log_timer_creation_db(&conn, &original_duration_str, &new_message, false).unwrap();
                 println!("Restarting timer...");
                 duration = parse_duration(&original_duration_str).unwrap();
                 continue;
             },
-            TimerAction::Stop => {
+            TimerAction::Stop | TimerAction::Done { .. } | TimerAction::Timeout => {
                 println!("Stopping timer.");
                 unregister_active_timer_db(&conn, active_timer_id).unwrap();
                 break;
@@ -1162,7 +2671,130 @@ fn main() {
         run_popup();
         return;
     }
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    // `tt again` fills in the arguments of the timer it repeats, then starts it like any other.
+    if let Some(Commands::Again { id, fg, bg }) = args.command {
+        fn read_failed<T>(e: rusqlite::Error) -> T {
+            eprintln!("Could not read the history: {}", e);
+            process::exit(1);
+        }
+        let conn = init_db().unwrap_or_else(read_failed);
+        let Some((entry, run)) = repeat_run_db(&conn, id).unwrap_or_else(read_failed) else {
+            match id {
+                Some(id) => eprintln!("No timer with id {} in the history. See `tt history` for ids.", id),
+                None => eprintln!("No timers in the history yet."),
+            }
+            process::exit(1);
+        };
+        // Snoozes and restarts repeat the timer they belong to, so it gets its full length again.
+        let Some(repeat) = repeat_timer_db(&conn, run).unwrap_or_else(read_failed) else {
+            eprintln!("Timer {} is a snooze or restart of timer {}, which is no longer in the history.", entry, run);
+            process::exit(1);
+        };
+        args.duration = Some(repeat.duration);
+        args.message = Some(repeat.message).filter(|m| !m.is_empty());
+        args.fg = (fg || repeat.fg) && !bg;
+        args.tags = repeat.labels.flag_tags;
+        args.project = repeat.labels.project;
+        // A sound that no longer exists falls back to the default.
+        args.sound = repeat.sound.filter(|name| sounds::find(name).is_some());
+        args.say = repeat.say;
+        args.block = repeat.block;
+        args.command = None;
+    }
+
+    match &args.command {
+        Some(Commands::Sounds { action }) => {
+            match action {
+                Some(SoundsCommand::Preview { name }) => preview_sound(name),
+                Some(SoundsCommand::List) | None => list_sounds(),
+            }
+            return;
+        }
+        Some(Commands::Tray) => {
+            tray::run();
+            return;
+        }
+        Some(Commands::List { format }) => {
+            list::run(format);
+            return;
+        }
+        Some(Commands::History { action: Some(HistoryCommand::Export { format, output, filter, paging }), .. }) => {
+            export::run(filter, paging, format, output.as_deref());
+            return;
+        }
+        Some(Commands::History { action: Some(HistoryCommand::Import { file, format, dry_run }), .. }) => {
+            import::run(file, format.as_deref(), *dry_run);
+            return;
+        }
+        Some(Commands::History { action: Some(HistoryCommand::Delete { ids }), .. }) => {
+            let conn = init_db().expect("Failed to initialize database");
+            let mut ids = ids.clone();
+            ids.sort_unstable();
+            ids.dedup();
+            let deleted = history::delete(&conn, &ids).expect("Failed to delete history entries");
+            if deleted < ids.len() {
+                eprintln!("{}", color(&format!("{} of {} entries not found.", ids.len() - deleted, ids.len()), "gray"));
+            }
+            println!("{} Deleted {} history entr{}.", color("✓", "green"), deleted, if deleted == 1 { "y" } else { "ies" });
+            if deleted < ids.len() {
+                process::exit(1);
+            }
+            return;
+        }
+        Some(Commands::History { action: Some(HistoryCommand::Prune { older_than }), .. }) => {
+            let conn = init_db().expect("Failed to initialize database");
+            let before = Local::now() - chrono::Duration::from_std(*older_than).unwrap_or_default();
+            let deleted = history::prune(&conn, before.naive_local()).expect("Failed to prune history");
+            println!(
+                "{} Deleted {} history entr{} from before {}.",
+                color("✓", "green"),
+                deleted,
+                if deleted == 1 { "y" } else { "ies" },
+                before.format("%Y-%m-%d %H:%M")
+            );
+            return;
+        }
+        Some(Commands::History { action: Some(HistoryCommand::Clear { yes }), .. }) => {
+            let conn = init_db().expect("Failed to initialize database");
+            if !yes {
+                let count: i64 = conn.query_row("SELECT COUNT(*) FROM timer_history", [], |row| row.get(0)).unwrap_or_default();
+                eprintln!("This deletes all {} history entries. Run `tt history clear --yes` to confirm.", count);
+                process::exit(1);
+            }
+            let deleted = history::clear(&conn).expect("Failed to clear history");
+            println!("{} Deleted {} history entr{}.", color("✓", "green"), deleted, if deleted == 1 { "y" } else { "ies" });
+            return;
+        }
+        Some(Commands::History { action: None, filter, paging }) => {
+            let paging = history::Paging { limit: Some(paging.limit.unwrap_or(20)), ..paging.clone() };
+            match show_history_db(filter, &paging) {
+                Ok(true) => {
+                    println!("{}", color(&format!("More: add --page {}", paging.page + 1), "gray"));
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("Could not read the history: {}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Commands::Report { by, json, heatmap, filter }) => {
+            report::run(filter, by, *json, *heatmap);
+            return;
+        }
+        Some(Commands::Prompt) => {
+            prompt::run();
+            return;
+        }
+        Some(Commands::Status { format, watch, interval }) => {
+            status::run(format, *watch, *interval);
+            return;
+        }
+        Some(Commands::Again { .. }) | None => {}
+    }
     
     // Check for updates
     if args.update {
@@ -1170,12 +2802,14 @@ fn main() {
         return;
     }
     
-    if let Some(count) = args.logs {
-        show_history_db(count).unwrap();
+    if args.logs.is_some() || args.search.is_some() {
+        let filter = history::HistoryFilter { grep: args.search.clone(), ..Default::default() };
+        let paging = history::Paging { limit: Some(args.logs.unwrap_or(10)), ..Default::default() };
+        show_history_db(&filter, &paging).unwrap();
         return;
     }
     if args.active {
-        show_active_timer_db().unwrap();
+        dashboard::run().unwrap();
         return;
     }
     if args.kill {
@@ -1189,7 +2823,7 @@ fn main() {
                 return default_dur;
             }
         }
-        eprintln!("Duration string required unless using --logs (-l), --active (-a), --kill, or --update (-u)");
+        eprintln!("Duration string required unless using --logs (-l), --active (-a), --kill, --update (-u), or a subcommand");
         process::exit(1);
     });
     let duration = match parse_duration(&duration_str) {
@@ -1200,6 +2834,20 @@ fn main() {
         }
     };
     let popup_message = args.message.unwrap_or_else(|| "".to_string());
+    let labels = Labels::new(&args.tags, args.project.as_deref(), &popup_message);
+    let sound = match &args.sound {
+        Some(name) => sounds::find(name).unwrap_or_else(|| {
+            eprintln!("Unknown sound '{}'. Available sounds: {}", name, sounds::names());
+            process::exit(1);
+        }),
+        None => sounds::default_sound(),
+    };
+    let alarm = AlarmOptions {
+        sound,
+        sound_given: args.sound.is_some(),
+        say: args.say || std::env::var("TT_SAY").map(|v| v == "1").unwrap_or(false),
+        block: args.block,
+    };
     
     // Calculate start and end time for display
     let start_time = chrono::Local::now();
@@ -1211,15 +2859,20 @@ fn main() {
     if args.fg || args.background_child {
         let conn = init_db().expect("Failed to initialize database");
         // Only log to DB if not background child (parent already logged it)
-        if !args.background_child {
-            log_timer_creation_db(&conn, &duration_str, &popup_message, args.fg).unwrap();
+        let history_id = if args.background_child {
+            args.history_id
+        } else {
+            let _ = history::apply_retention(&conn);
+            let id = log_timer_creation_db(&conn, &duration_str, &popup_message, args.fg, &labels, &alarm, None).unwrap();
             print_timer_started_box(&duration_str, &popup_message, &start_time_str, &end_time_str, false);
-        }
-        run_timer(duration, duration_str, popup_message.clone(), args.fg);
+            Some(id)
+        };
+        run_timer(duration, duration_str, popup_message.clone(), args.fg, history_id, &alarm, &labels);
     } else {
         // Log timer creation from parent before spawning
         let conn = init_db().expect("Failed to initialize database");
-        log_timer_creation_db(&conn, &duration_str, &popup_message, false).unwrap();
+        let _ = history::apply_retention(&conn);
+        let history_id = log_timer_creation_db(&conn, &duration_str, &popup_message, false, &labels, &alarm, None).unwrap();
         
         // Background mode: spawn a detached child process (cross-platform)
         let exe = std::env::current_exe().expect("Failed to get current executable path");
@@ -1231,6 +2884,8 @@ fn main() {
             let mut cmd = Command::new(&exe);
             cmd.arg(&duration_str)
                .arg("--background-child")
+               .arg("--history-id")
+               .arg(history_id.to_string())
                .stdin(std::process::Stdio::null())
                .stdout(std::process::Stdio::null())
                .stderr(std::process::Stdio::null());
@@ -1238,6 +2893,21 @@ fn main() {
             if !popup_message.is_empty() {
                 cmd.arg(&popup_message);
             }
+            if alarm.sound_given {
+                cmd.arg("--sound").arg(sound.name);
+            }
+            if alarm.say {
+                cmd.arg("--say");
+            }
+            if alarm.block {
+                cmd.arg("--block");
+            }
+            for tag in &labels.flag_tags {
+                cmd.arg("--tag").arg(tag);
+            }
+            if let Some(project) = &labels.project {
+                cmd.arg("--project").arg(project);
+            }
             
             // Create a new session to fully detach from terminal
             unsafe {
@@ -1263,6 +2933,8 @@ fn main() {
             let mut cmd = Command::new(&exe);
             cmd.arg(&duration_str)
                .arg("--background-child")
+               .arg("--history-id")
+               .arg(history_id.to_string())
                .stdout(std::process::Stdio::null())
                .stderr(std::process::Stdio::null())
                .stdin(std::process::Stdio::null());
@@ -1270,6 +2942,21 @@ fn main() {
             if !popup_message.is_empty() {
                 cmd.arg(&popup_message);
             }
+            if alarm.sound_given {
+                cmd.arg("--sound").arg(sound.name);
+            }
+            if alarm.say {
+                cmd.arg("--say");
+            }
+            if alarm.block {
+                cmd.arg("--block");
+            }
+            for tag in &labels.flag_tags {
+                cmd.arg("--tag").arg(tag);
+            }
+            if let Some(project) = &labels.project {
+                cmd.arg("--project").arg(project);
+            }
             
             const DETACHED_PROCESS: u32 = 0x00000008;
             const CREATE_NO_WINDOW: u32 = 0x08000000;
@@ -1287,3 +2974,34 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn args_are_consistent() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn message_named_like_a_subcommand() {
+        let args = Args::try_parse_from(["tt", "5m", "list"]).unwrap();
+        assert_eq!(args.duration.as_deref(), Some("5m"));
+        assert_eq!(args.message.as_deref(), Some("list"));
+        assert!(args.command.is_none());
+
+        let args = Args::try_parse_from(["tt", "-f", "25m", "report"]).unwrap();
+        assert!(args.fg);
+        assert_eq!(args.message.as_deref(), Some("report"));
+        assert!(args.command.is_none());
+    }
+
+    #[test]
+    fn subcommands_without_a_duration() {
+        let args = Args::try_parse_from(["tt", "list"]).unwrap();
+        assert!(matches!(args.command, Some(Commands::List { .. })));
+        assert!(args.duration.is_none());
+    }
+}
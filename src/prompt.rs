@@ -0,0 +1,98 @@
+//! `tt prompt`: the nearest timer for a shell prompt, without touching the database.
+//!
+//! Prompt hooks run on every command, so instead of opening SQLite `tt prompt` reads a small
+//! cache file next to the database. Everything that changes active_timers rewrites the cache
+//! (see `refresh_cache`), and the prompt works the countdowns out from the stored end times.
+//!
+//! One line per active timer, tab separated:
+//! `id  pid  state  seconds  total  duration  message`, where state is `run` (seconds is the
+//! Unix time it goes off) or `pause` (seconds is the time left).
+
+use crate::ActiveTimer;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn cache_path() -> String {
+    let db = crate::db_path();
+    format!("{}.prompt", db.strip_suffix(".db").unwrap_or(&db))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Rewrites the cache from active_timers. Called after every change to the table; errors are
+/// ignored since the prompt is only a convenience.
+pub fn refresh_cache(conn: &rusqlite::Connection) {
+    let Ok(timers) = crate::load_active_timers_db(conn) else { return };
+    let now = unix_now();
+    let mut cache = String::new();
+    for timer in &timers {
+        let (state, seconds) = if timer.paused {
+            ("pause", timer.remaining.as_secs())
+        } else {
+            ("run", (now + timer.remaining.as_secs()).saturating_sub(timer.overtime.as_secs()))
+        };
+        let message = timer.message.replace(['\t', '\n', '\r'], " ");
+        cache.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            timer.id,
+            timer.pid,
+            state,
+            seconds,
+            timer.total.as_secs(),
+            timer.duration,
+            message
+        ));
+    }
+    // Write then rename, so a prompt never reads a half-written file.
+    let path = cache_path();
+    let tmp = format!("{}.{}", path, std::process::id());
+    if std::fs::write(&tmp, cache).is_ok() && std::fs::rename(&tmp, &path).is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+}
+
+/// Reads the cache back into timers, skipping ones whose process is gone.
+fn read_cache() -> Vec<ActiveTimer> {
+    let Ok(cache) = std::fs::read_to_string(cache_path()) else { return Vec::new() };
+    let now = unix_now();
+    cache
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(7, '\t');
+            let id = fields.next()?.parse().ok()?;
+            let pid = fields.next()?.parse().ok()?;
+            let paused = fields.next()? == "pause";
+            let seconds: u64 = fields.next()?.parse().ok()?;
+            let total = Duration::from_secs(fields.next()?.parse().ok()?);
+            let duration = fields.next()?.to_string();
+            let message = fields.next().unwrap_or_default().to_string();
+            let (remaining, overtime) = if paused {
+                (seconds, 0)
+            } else {
+                (seconds.saturating_sub(now), now.saturating_sub(seconds))
+            };
+            Some(ActiveTimer {
+                id,
+                pid,
+                duration,
+                message,
+                total,
+                remaining: Duration::from_secs(remaining),
+                paused,
+                overtime: Duration::from_secs(overtime),
+                tags: Vec::new(),
+                project: None,
+            })
+        })
+        .filter(|t| crate::process_alive(t.pid))
+        .collect()
+}
+
+/// Prints the nearest timer the same way as `tt status`, or nothing when no timer is active.
+pub fn run() {
+    let timers = read_cache();
+    if !timers.is_empty() {
+        println!("{}", crate::status::text(&timers));
+    }
+}
@@ -0,0 +1,383 @@
+//! `tt report`: focus time and habits, summarized from timer_history.
+//!
+//! Focus time is how long each timer run lasted (its planned length if it never finished),
+//! including restarts but not snoozes (those are time away from the task). The report takes the same filters as `tt history`.
+
+use crate::color;
+use crate::history::{self, HistoryEntry, HistoryFilter, Paging};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal, Write};
+
+const BAR_WIDTH: usize = 30;
+
+/// A timer_history row, read for the report.
+struct Run {
+    start: NaiveDateTime,
+    seconds: u64,
+    message: String,
+    tags: Vec<String>,
+    project: Option<String>,
+    outcome: Option<String>,
+    /// A snooze of an earlier run rather than time spent on the task.
+    snooze: bool,
+}
+
+impl Run {
+    fn new(entry: &HistoryEntry) -> Option<Self> {
+        let start = NaiveDateTime::parse_from_str(&entry.timestamp, "%Y-%m-%d %H:%M:%S").ok()?;
+        // A timer stopped early counts only the time it ran, one left ringing counts the overtime.
+        let elapsed = entry
+            .finished
+            .as_deref()
+            .and_then(|finished| NaiveDateTime::parse_from_str(finished, "%Y-%m-%d %H:%M:%S").ok())
+            .map(|finished| (finished - start).num_seconds().max(0) as u64);
+        let planned = || humantime::parse_duration(&entry.duration).map(|d| d.as_secs()).unwrap_or_default();
+        Some(Run {
+            start,
+            seconds: elapsed.unwrap_or_else(planned),
+            message: entry.message.clone(),
+            tags: entry.tags.clone(),
+            project: entry.project.clone(),
+            outcome: entry.outcome.clone(),
+            snooze: entry.message.starts_with("(Snoozed) "),
+        })
+    }
+}
+
+/// The text a message starts with, before any ':' or ' - ', without the snooze/restart
+/// marker and #tags. "Review: PR 12 #work" and "Review: PR 15" both give "Review".
+fn message_prefix(message: &str) -> String {
+    let message = message.strip_prefix("(Restarted) ").unwrap_or(message);
+    let words: Vec<&str> = message.split_whitespace().filter(|w| !w.starts_with('#')).collect();
+    let text = words.join(" ");
+    let prefix = text.split(':').next().unwrap_or_default();
+    let prefix = prefix.split(" - ").next().unwrap_or_default().trim();
+    if prefix.is_empty() { "(no message)".to_string() } else { prefix.to_string() }
+}
+
+/// The groups a run counts towards for `--by`.
+fn group_keys(run: &Run, by: &str) -> Vec<String> {
+    let date = run.start.date();
+    match by {
+        "week" => {
+            let monday = date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+            vec![format!("{}-W{:02} ({})", date.iso_week().year(), date.iso_week().week(), monday.format("%b %-d"))]
+        }
+        "month" => vec![date.format("%Y-%m %B").to_string()],
+        "tag" if run.tags.is_empty() => vec!["(untagged)".to_string()],
+        "tag" => run.tags.iter().map(|t| format!("#{}", t)).collect(),
+        "project" => vec![run.project.clone().unwrap_or_else(|| "(no project)".to_string())],
+        "message" => vec![message_prefix(&run.message)],
+        _ => vec![date.format("%Y-%m-%d %a").to_string()],
+    }
+}
+
+/// "2h 05m", "45m" or "30s".
+fn format_hm(seconds: u64) -> String {
+    match seconds {
+        s if s >= 3600 => format!("{}h {:02}m", s / 3600, (s % 3600) / 60),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Colors text for the terminal; output to a pipe or file stays plain.
+fn tint(text: &str, name: &str, paint: bool) -> String {
+    if paint { color(text, name) } else { text.to_string() }
+}
+
+fn bar(value: u64, max: u64, paint: bool) -> String {
+    let filled = if max == 0 { 0 } else { ((value as f64 / max as f64) * BAR_WIDTH as f64).round() as usize };
+    tint(&"█".repeat(filled.max(usize::from(value > 0))), "cyan", paint)
+}
+
+#[derive(Serialize)]
+struct Group {
+    key: String,
+    seconds: u64,
+    timers: usize,
+}
+
+#[derive(Serialize)]
+struct Streak {
+    days: usize,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    /// First and last day with a timer, as YYYY-MM-DD.
+    from: Option<String>,
+    to: Option<String>,
+    /// Timer runs, not counting snoozes.
+    timers: usize,
+    focus_seconds: u64,
+    by: String,
+    groups: Vec<Group>,
+    /// How many runs (snoozes included) ended each way; "none" for ones that never finished.
+    outcomes: BTreeMap<String, usize>,
+    /// Share of done among runs answered with done or stopped, 0-1.
+    completion_rate: Option<f64>,
+    snoozes: usize,
+    average_snoozes: f64,
+    longest_streak: Streak,
+    current_streak: usize,
+}
+
+fn day(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Longest run of consecutive days in `days` (sorted, distinct), and the current one
+/// (ending today or yesterday).
+fn streaks(days: &[NaiveDate]) -> (Streak, usize) {
+    let mut longest = Streak { days: 0, from: None, to: None };
+    let mut start = 0;
+    for i in 0..days.len() {
+        if i > 0 && days[i - 1].succ_opt() != Some(days[i]) {
+            start = i;
+        }
+        if i + 1 - start > longest.days {
+            longest = Streak { days: i + 1 - start, from: Some(day(days[start])), to: Some(day(days[i])) };
+        }
+    }
+    let today = Local::now().date_naive();
+    let mut current = 0;
+    if let Some(&last) = days.last() {
+        if last == today || last.succ_opt() == Some(today) {
+            current = days.iter().rev().zip(0..).take_while(|(day, i)| last - chrono::Duration::days(*i) == **day).count();
+        }
+    }
+    (longest, current)
+}
+
+fn build(runs: &[Run], by: &str) -> Report {
+    let focus: Vec<&Run> = runs.iter().filter(|r| !r.snooze).collect();
+
+    let mut groups: BTreeMap<String, Group> = BTreeMap::new();
+    for run in &focus {
+        for key in group_keys(run, by) {
+            let group = groups.entry(key.clone()).or_insert(Group { key, seconds: 0, timers: 0 });
+            group.seconds += run.seconds;
+            group.timers += 1;
+        }
+    }
+    let mut groups: Vec<Group> = groups.into_values().collect();
+    if ["tag", "project", "message"].contains(&by) {
+        groups.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.key.cmp(&b.key)));
+    }
+
+    let mut outcomes = BTreeMap::new();
+    for run in runs {
+        *outcomes.entry(run.outcome.clone().unwrap_or_else(|| "none".into())).or_insert(0) += 1;
+    }
+    let done = outcomes.get("done").copied().unwrap_or(0);
+    let stopped = outcomes.get("stopped").copied().unwrap_or(0) + outcomes.get("auto-stopped").copied().unwrap_or(0);
+    let snoozes = outcomes.get("snoozed").copied().unwrap_or(0) + outcomes.get("auto-snoozed").copied().unwrap_or(0);
+
+    let mut days: Vec<NaiveDate> = focus.iter().map(|r| r.start.date()).collect();
+    days.sort();
+    days.dedup();
+    let (longest_streak, current_streak) = streaks(&days);
+
+    Report {
+        from: days.first().copied().map(day),
+        to: days.last().copied().map(day),
+        timers: focus.len(),
+        focus_seconds: focus.iter().map(|r| r.seconds).sum(),
+        by: by.to_string(),
+        groups,
+        outcomes,
+        completion_rate: (done + stopped > 0).then(|| done as f64 / (done + stopped) as f64),
+        snoozes,
+        average_snoozes: if focus.is_empty() { 0.0 } else { snoozes as f64 / focus.len() as f64 },
+        longest_streak,
+        current_streak,
+    }
+}
+
+fn print(out: &mut impl Write, report: &Report, paint: bool) -> io::Result<()> {
+    let (Some(from), Some(to)) = (&report.from, &report.to) else {
+        return writeln!(out, "{}", tint("No timers to report on.", "gray", paint));
+    };
+    writeln!(
+        out,
+        "{} timers · {} focus · {} – {}",
+        report.timers,
+        format_hm(report.focus_seconds),
+        from,
+        to
+    )?;
+
+    writeln!(out)?;
+    writeln!(out, "{}", tint(&format!("Focus time by {}", report.by), "gray", paint))?;
+    let width = report.groups.iter().map(|g| g.key.chars().count()).max().unwrap_or(0).min(32);
+    let max = report.groups.iter().map(|g| g.seconds).max().unwrap_or(0);
+    for group in &report.groups {
+        let key: String = group.key.chars().take(width).collect();
+        writeln!(out, "{:<width$}  {:>8}  {:>3}×  {}", key, format_hm(group.seconds), group.timers, bar(group.seconds, max, paint), width = width)?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "{}", tint("Outcomes", "gray", paint))?;
+    let total: usize = report.outcomes.values().sum();
+    let max = report.outcomes.values().copied().max().unwrap_or(0);
+    for (outcome, count) in &report.outcomes {
+        let percent = *count as f64 * 100.0 / total.max(1) as f64;
+        writeln!(out, "{:<13} {:>5}  {:>3.0}%  {}", outcome, count, percent, bar(*count as u64, max as u64, paint))?;
+    }
+
+    writeln!(out)?;
+    if let Some(rate) = report.completion_rate {
+        writeln!(out, "Completed     {:.0}% (done vs stopped)", rate * 100.0)?;
+    }
+    writeln!(out, "Snoozes       {} ({:.1} per timer)", report.snoozes, report.average_snoozes)?;
+    let streak = &report.longest_streak;
+    if let (Some(from), Some(to)) = (&streak.from, &streak.to) {
+        writeln!(
+            out,
+            "Streak        longest {} day{} ({} – {}), current {} day{}",
+            streak.days,
+            if streak.days == 1 { "" } else { "s" },
+            from,
+            to,
+            report.current_streak,
+            if report.current_streak == 1 { "" } else { "s" }
+        )?;
+    }
+    Ok(())
+}
+
+/// 256-color codes for the heatmap: no activity, then four levels from least to most.
+fn heatmap_colors() -> [&'static str; 5] {
+    match crate::theme::terminal_theme() {
+        crate::theme::Theme::Dark => ["237", "22", "28", "34", "40"],
+        crate::theme::Theme::Light => ["254", "151", "114", "71", "28"],
+    }
+}
+
+#[derive(Serialize)]
+struct HeatmapDay {
+    date: String,
+    seconds: u64,
+}
+
+/// Focus time per day from `from` to `to`, both included.
+fn daily_seconds(runs: &[Run], from: NaiveDate, to: NaiveDate) -> BTreeMap<NaiveDate, u64> {
+    let mut days: BTreeMap<NaiveDate, u64> = from.iter_days().take_while(|d| *d <= to).map(|d| (d, 0)).collect();
+    for run in runs.iter().filter(|r| !r.snooze) {
+        if let Some(seconds) = days.get_mut(&run.start.date()) {
+            *seconds += run.seconds;
+        }
+    }
+    days
+}
+
+/// A GitHub-style grid: one column per week, one row per weekday, shaded by focus time.
+/// Levels split the active days into quarters, so the shades adapt to how much you use tt.
+/// Without a terminal the levels are drawn with shade characters instead of colors.
+fn print_heatmap(out: &mut impl Write, days: &BTreeMap<NaiveDate, u64>, paint: bool) -> io::Result<()> {
+    let (Some(&from), Some(&to)) = (days.keys().next(), days.keys().next_back()) else { return Ok(()) };
+    let colors = heatmap_colors();
+    let cell = |level: usize| if paint { color("■", colors[level]) } else { ["·", "░", "▒", "▓", "█"][level].to_string() };
+    let mut active: Vec<u64> = days.values().copied().filter(|&s| s > 0).collect();
+    active.sort_unstable();
+    let quartile = |q: usize| active.get((active.len() * q / 4).min(active.len().saturating_sub(1))).copied().unwrap_or(0);
+    let thresholds = [quartile(1), quartile(2), quartile(3)];
+    let level = |seconds: u64| match seconds {
+        0 => 0,
+        s if s <= thresholds[0] => 1,
+        s if s <= thresholds[1] => 2,
+        s if s <= thresholds[2] => 3,
+        _ => 4,
+    };
+
+    let first_monday = from - chrono::Duration::days(from.weekday().num_days_from_monday() as i64);
+    let weeks = ((to - first_monday).num_days() / 7 + 1) as usize;
+    // Leave a gap between cells when the terminal is wide enough.
+    let terminal_width = crossterm::terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
+    let cell_width = if 4 + weeks * 2 <= terminal_width { 2 } else { 1 };
+
+    let mut months = String::new();
+    let mut last_month = None;
+    for week in 0..weeks {
+        let monday = first_monday + chrono::Duration::weeks(week as i64);
+        if last_month == Some(monday.month()) {
+            continue;
+        }
+        // Skip a label that would run into the previous one rather than shift it.
+        if months.is_empty() || months.chars().count() < week * cell_width {
+            months.push_str(&" ".repeat(week * cell_width - months.chars().count()));
+            months.push_str(&monday.format("%b").to_string());
+        }
+        last_month = Some(monday.month());
+    }
+    writeln!(out, "    {}", tint(&months, "gray", paint))?;
+
+    for (weekday, label) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().enumerate() {
+        let mut line = tint(&format!("{:<4}", label), "gray", paint);
+        for week in 0..weeks {
+            let date = first_monday + chrono::Duration::days((week * 7 + weekday) as i64);
+            let cell = match days.get(&date) {
+                Some(&seconds) => cell(level(seconds)),
+                None => " ".to_string(),
+            };
+            line.push_str(&cell);
+            if cell_width == 2 {
+                line.push(' ');
+            }
+        }
+        writeln!(out, "{}", line)?;
+    }
+
+    let legend: String = (0..colors.len()).map(cell).collect::<Vec<_>>().join(" ");
+    writeln!(out, "    {} {} {}", tint("Less", "gray", paint), legend, tint("More", "gray", paint))?;
+    let total: u64 = days.values().sum();
+    let busiest = days.iter().max_by_key(|(date, seconds)| (**seconds, std::cmp::Reverse(**date)));
+    let mut summary = format!("{} focus on {} of {} days", format_hm(total), active.len(), days.len());
+    if let Some((date, &seconds)) = busiest.filter(|(_, s)| **s > 0) {
+        summary.push_str(&format!(" · busiest {} ({})", date.format("%Y-%m-%d"), format_hm(seconds)));
+    }
+    writeln!(out)?;
+    writeln!(out, "{}", summary)
+}
+
+pub fn run(filter: &HistoryFilter, by: &str, json: bool, heatmap: bool) {
+    if !["day", "week", "month", "tag", "project", "message"].contains(&by) {
+        eprintln!("Unknown grouping '{}'. Use day, week, month, tag, project or message.", by);
+        std::process::exit(2);
+    }
+    let entries = match crate::init_db().and_then(|conn| history::query(&conn, filter, &Paging::default())) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Could not read the history: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let runs: Vec<Run> = entries.iter().filter_map(Run::new).collect();
+    let mut out = std::io::stdout().lock();
+    let paint = out.is_terminal();
+    // Errors here mean the reader closed the pipe (`tt report | head`), so they are dropped.
+    let _ = if heatmap {
+        // The last year unless --since/--until say otherwise.
+        let to = filter.until.map(|t| (t - chrono::Duration::seconds(1)).date()).unwrap_or_else(|| Local::now().date_naive());
+        let from = filter.since.map(|t| t.date()).unwrap_or(to - chrono::Duration::days(364));
+        let days = daily_seconds(&runs, from, to);
+        if json {
+            let days: Vec<HeatmapDay> = days.iter().map(|(d, &seconds)| HeatmapDay { date: day(*d), seconds }).collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&days).unwrap_or_default())
+        } else {
+            print_heatmap(&mut out, &days, paint)
+        }
+    } else {
+        let report = build(&runs, by);
+        if json {
+            writeln!(out, "{}", serde_json::to_string_pretty(&report).unwrap_or_default())
+        } else {
+            print(&mut out, &report, paint)
+        }
+    };
+}
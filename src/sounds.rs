@@ -0,0 +1,154 @@
+//! Built-in alert sounds.
+//!
+//! The "loop" sound is the embedded MP3 under `sounds/`. The others are short tones that are
+//! synthesized into an in-memory WAV file, so they add nothing to the binary size and play
+//! through the same decoder as the MP3.
+
+use std::sync::OnceLock;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+/// A named alert sound that can be selected with `--sound` or TT_SOUND.
+pub struct Sound {
+    pub name: &'static str,
+    pub description: &'static str,
+    kind: SoundKind,
+}
+
+enum SoundKind {
+    Embedded(&'static [u8]),
+    Chime,
+    Bell,
+    Beep,
+}
+
+/// All built-in sounds, in the order they are listed by `tt sounds list`.
+pub const SOUNDS: &[Sound] = &[
+    Sound {
+        name: "loop",
+        description: "Calm looping melody (default)",
+        kind: SoundKind::Embedded(include_bytes!("../sounds/calm-loop-80576.mp3")),
+    },
+    Sound {
+        name: "chime",
+        description: "Soft two-note chime",
+        kind: SoundKind::Chime,
+    },
+    Sound {
+        name: "bell",
+        description: "Ringing desk bell",
+        kind: SoundKind::Bell,
+    },
+    Sound {
+        name: "beep",
+        description: "Classic alarm clock beeps",
+        kind: SoundKind::Beep,
+    },
+];
+
+/// Looks up a built-in sound by name (case-insensitive).
+pub fn find(name: &str) -> Option<&'static Sound> {
+    SOUNDS.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+}
+
+/// Returns the sound selected by the TT_SOUND environment variable, falling back to "loop".
+pub fn default_sound() -> &'static Sound {
+    std::env::var("TT_SOUND")
+        .ok()
+        .and_then(|name| find(&name))
+        .unwrap_or(&SOUNDS[0])
+}
+
+/// Comma separated list of sound names, for error messages.
+pub fn names() -> String {
+    SOUNDS.iter().map(|s| s.name).collect::<Vec<_>>().join(", ")
+}
+
+impl Sound {
+    /// Encoded audio for one repetition of the sound. Synthesized sounds are rendered the
+    /// first time they are asked for and kept for the rest of the run.
+    pub fn data(&self) -> &'static [u8] {
+        static CHIME: OnceLock<Vec<u8>> = OnceLock::new();
+        static BELL: OnceLock<Vec<u8>> = OnceLock::new();
+        static BEEP: OnceLock<Vec<u8>> = OnceLock::new();
+        match self.kind {
+            SoundKind::Embedded(bytes) => bytes,
+            SoundKind::Chime => CHIME.get_or_init(|| encode_wav(&chime())),
+            SoundKind::Bell => BELL.get_or_init(|| encode_wav(&bell())),
+            SoundKind::Beep => BEEP.get_or_init(|| encode_wav(&beep())),
+        }
+    }
+
+    /// File extension matching the format returned by `data()`.
+    pub fn extension(&self) -> &'static str {
+        match self.kind {
+            SoundKind::Embedded(_) => "mp3",
+            _ => "wav",
+        }
+    }
+}
+
+/// A decaying sine tone starting at `start` seconds, mixed into `samples`.
+fn add_tone(samples: &mut [f32], start: f32, freq: f32, length: f32, decay: f32, gain: f32) {
+    let first = (start * SAMPLE_RATE as f32) as usize;
+    let count = (length * SAMPLE_RATE as f32) as usize;
+    for i in 0..count {
+        let Some(sample) = samples.get_mut(first + i) else { break };
+        let t = i as f32 / SAMPLE_RATE as f32;
+        // Short linear attack avoids a click at the start of each note.
+        let attack = (t / 0.005).min(1.0);
+        let envelope = attack * (-decay * t).exp();
+        *sample += gain * envelope * (2.0 * std::f32::consts::PI * freq * t).sin();
+    }
+}
+
+fn silence(seconds: f32) -> Vec<f32> {
+    vec![0.0; (seconds * SAMPLE_RATE as f32) as usize]
+}
+
+fn chime() -> Vec<f32> {
+    let mut samples = silence(2.0);
+    add_tone(&mut samples, 0.0, 1318.5, 0.9, 4.0, 0.4); // E6
+    add_tone(&mut samples, 0.35, 1046.5, 1.2, 3.5, 0.4); // C6
+    samples
+}
+
+fn bell() -> Vec<f32> {
+    let mut samples = silence(2.5);
+    // A struck bell has inharmonic partials that die away at different rates.
+    for (ratio, decay, gain) in [(1.0, 2.5, 0.35), (2.76, 4.0, 0.2), (5.4, 6.0, 0.1), (8.93, 9.0, 0.05)] {
+        add_tone(&mut samples, 0.0, 880.0 * ratio, 2.5, decay, gain);
+    }
+    samples
+}
+
+fn beep() -> Vec<f32> {
+    let mut samples = silence(1.2);
+    for i in 0..4 {
+        add_tone(&mut samples, i as f32 * 0.15, 2000.0, 0.08, 0.0, 0.3);
+    }
+    samples
+}
+
+/// Encodes mono f32 samples as a 16-bit PCM WAV file.
+fn encode_wav(samples: &[f32]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    wav
+}
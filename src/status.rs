@@ -0,0 +1,118 @@
+//! `tt status`: a one-line status for the nearest active timer, for tmux, waybar and polybar.
+//!
+//! Without `--watch` it prints once and exits (tmux `#(tt status)`, polybar `exec`). With
+//! `--watch` it keeps running and prints a new line whenever the status changes, which is what
+//! waybar's and polybar's streaming modes expect.
+
+use crate::{format_countdown, ActiveTimer, TimerState};
+use serde::Serialize;
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+/// The waybar custom module format.
+#[derive(Serialize)]
+struct Waybar {
+    text: String,
+    tooltip: String,
+    /// "running", "paused", "ringing" or "idle", for styling in waybar's CSS.
+    class: &'static str,
+    /// How much of the countdown has elapsed, 0-100.
+    percentage: u8,
+}
+
+/// The timer to show: a ringing one first, then the running one that goes off soonest,
+/// then a paused one.
+fn nearest(timers: &[ActiveTimer]) -> Option<&ActiveTimer> {
+    timers
+        .iter()
+        .find(|t| t.ringing())
+        .or_else(|| timers.iter().filter(|t| !t.paused).min_by_key(|t| t.remaining))
+        .or_else(|| timers.first())
+}
+
+fn label(timer: &ActiveTimer) -> &str {
+    if timer.message.is_empty() { &timer.duration } else { &timer.message }
+}
+
+/// "🍅 12:34 Focus", with "(+2)" when more timers are active.
+pub fn text(timers: &[ActiveTimer]) -> String {
+    let Some(timer) = nearest(timers) else { return String::new() };
+    let icon = match timer.state() {
+        TimerState::Paused => "⏸".to_string(),
+        TimerState::Ringing => "🔔".to_string(),
+        TimerState::Running => std::env::var("TT_STATUS_ICON").unwrap_or_else(|_| "🍅".to_string()),
+    };
+    let time = if timer.ringing() {
+        format!("+{}", format_countdown(timer.overtime))
+    } else {
+        format_countdown(timer.remaining)
+    };
+    let mut text = format!("{} {} {}", icon, time, label(timer)).trim().to_string();
+    if timers.len() > 1 {
+        text.push_str(&format!(" (+{})", timers.len() - 1));
+    }
+    text
+}
+
+fn waybar(timers: &[ActiveTimer]) -> String {
+    let tooltip = timers
+        .iter()
+        .map(|t| format!("#{} {} — {} ({})", t.id, label(t), format_countdown(t.remaining), t.state().as_str()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let (class, percentage) = match nearest(timers) {
+        Some(timer) => (timer.state().as_str(), timer.percent()),
+        None => ("idle", 0),
+    };
+    let status = Waybar {
+        text: text(timers),
+        tooltip: if tooltip.is_empty() { "No active timers".to_string() } else { tooltip },
+        class,
+        percentage,
+    };
+    serde_json::to_string(&status).unwrap_or_default()
+}
+
+/// Builds the status line in the given format ("text" or "waybar").
+fn render(conn: &rusqlite::Connection, format: &str) -> String {
+    let timers: Vec<ActiveTimer> = crate::load_active_timers_db(conn)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| crate::process_alive(t.pid))
+        .collect();
+    match format {
+        "waybar" => waybar(&timers),
+        _ => text(&timers),
+    }
+}
+
+pub fn run(format: &str, watch: bool, interval: Duration) {
+    if format != "text" && format != "waybar" {
+        eprintln!("Unknown format '{}'. Use text or waybar.", format);
+        std::process::exit(2);
+    }
+    let conn = match crate::init_db() {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Could not open the timer database: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if !watch {
+        println!("{}", render(&conn, format));
+        return;
+    }
+    let mut last = None;
+    loop {
+        let line = render(&conn, format);
+        if last.as_ref() != Some(&line) {
+            // Stop quietly once the bar closes the pipe.
+            let mut out = stdout().lock();
+            if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+                return;
+            }
+            last = Some(line);
+        }
+        std::thread::sleep(interval);
+    }
+}
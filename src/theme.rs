@@ -0,0 +1,256 @@
+//! Color themes for terminal output and the popup.
+//!
+//! TT_THEME selects "dark" (default), "light" or "system". Colors can be given as a
+//! palette name ("green"), a 256-color code ("114") or a hex value ("#7aa2f7"), and
+//! palette names can be remapped with TT_PALETTE, e.g. `TT_PALETTE="green=#9ece6a,gray=245"`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+/// The TT_THEME setting as written by the user: "dark", "light" or "system".
+pub fn configured() -> String {
+    std::env::var("TT_THEME")
+        .map(|t| t.to_lowercase())
+        .ok()
+        .filter(|t| t == "light" || t == "system")
+        .unwrap_or_else(|| "dark".to_string())
+}
+
+/// Theme for terminal output. "system" follows the terminal background reported in
+/// COLORFGBG (set by many terminals), and falls back to dark. Resolved once per process,
+/// since terminal output asks for it for every colored string.
+pub fn terminal_theme() -> Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    *THEME.get_or_init(|| match configured().as_str() {
+        "light" => Theme::Light,
+        "system" => {
+            let background = std::env::var("COLORFGBG")
+                .ok()
+                .and_then(|v| v.rsplit(';').next().and_then(|bg| bg.parse::<u8>().ok()));
+            match background {
+                Some(7) | Some(15) => Theme::Light,
+                _ => Theme::Dark,
+            }
+        }
+        _ => Theme::Dark,
+    })
+}
+
+/// A resolved color: either an entry of the 256-color palette or a 24-bit color.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorSpec {
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// 256-color codes for the named colors on each theme. The dark palette is the original
+/// pastel set; the light one uses deeper shades that stay readable on a white background.
+fn palette_code(name: &str, theme: Theme) -> Option<u8> {
+    let (dark, light) = match name {
+        "red" => (210, 160),
+        "green" => (151, 28),
+        "yellow" => (229, 136),
+        "blue" => (153, 25),
+        "magenta" => (219, 127),
+        "cyan" => (159, 30),
+        "orange" => (215, 166),
+        "purple" => (183, 91),
+        "pink" => (218, 162),
+        "gray" => (240, 245),
+        "white" => (255, 235),
+        _ => return None,
+    };
+    Some(if theme == Theme::Light { light } else { dark })
+}
+
+/// Looks up a TT_PALETTE override for a palette name. The variable is read once per process.
+fn palette_override(name: &str) -> Option<String> {
+    static PALETTE: OnceLock<Option<String>> = OnceLock::new();
+    let palette = PALETTE.get_or_init(|| std::env::var("TT_PALETTE").ok()).as_deref()?;
+    palette.split(',').find_map(|entry| {
+        let (key, value) = entry.split_once('=')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+    })
+}
+
+fn parse_hex(value: &str) -> Option<ColorSpec> {
+    let hex = value.strip_prefix('#')?;
+    // Checking the bytes first keeps the slicing below on char boundaries.
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(ColorSpec::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Parses a color value (palette name, 256-color code or hex) for the given theme.
+pub fn parse(value: &str, theme: Theme) -> Option<ColorSpec> {
+    let value = value.trim().to_lowercase();
+    if let Some(spec) = parse_hex(&value) {
+        return Some(spec);
+    }
+    if let Ok(code) = value.parse::<u8>() {
+        return Some(ColorSpec::Ansi(code));
+    }
+    // An override may point at a hex value or code, but not at another name.
+    if let Some(spec) = palette_override(&value).and_then(|o| parse_hex(&o).or_else(|| o.parse().ok().map(ColorSpec::Ansi))) {
+        return Some(spec);
+    }
+    palette_code(&value, theme).map(ColorSpec::Ansi)
+}
+
+impl ColorSpec {
+    /// ANSI escape sequence that sets this as the foreground color.
+    pub fn ansi_fg(self) -> String {
+        match self {
+            ColorSpec::Ansi(code) => format!("\x1B[38;5;{}m", code),
+            ColorSpec::Rgb(r, g, b) => format!("\x1B[38;2;{};{};{}m", r, g, b),
+        }
+    }
+
+    /// The color as RGB, converting 256-color codes with the standard xterm palette.
+    pub fn to_rgb(self) -> [u8; 3] {
+        match self {
+            ColorSpec::Rgb(r, g, b) => [r, g, b],
+            ColorSpec::Ansi(code) => ansi_to_rgb(code),
+        }
+    }
+}
+
+fn ansi_to_rgb(code: u8) -> [u8; 3] {
+    const BASIC: [[u8; 3]; 16] = [
+        [0, 0, 0], [128, 0, 0], [0, 128, 0], [128, 128, 0],
+        [0, 0, 128], [128, 0, 128], [0, 128, 128], [192, 192, 192],
+        [128, 128, 128], [255, 0, 0], [0, 255, 0], [255, 255, 0],
+        [0, 0, 255], [255, 0, 255], [0, 255, 255], [255, 255, 255],
+    ];
+    match code {
+        0..=15 => BASIC[code as usize],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let index = code - 16;
+            [level(index / 36), level((index / 6) % 6), level(index % 6)]
+        }
+        _ => {
+            let gray = 8 + (code - 232) * 10;
+            [gray, gray, gray]
+        }
+    }
+}
+
+/// Popup color overrides as configured by the user (TT_COLOR_POPUP_*). Values use the same
+/// syntax as terminal colors; unset or invalid values keep the theme default.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct PopupColorOverrides {
+    pub message: Option<String>,
+    pub text: Option<String>,
+    pub snooze: Option<String>,
+    pub restart: Option<String>,
+    pub stop: Option<String>,
+}
+
+impl PopupColorOverrides {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok();
+        PopupColorOverrides {
+            message: var("TT_COLOR_POPUP_MESSAGE"),
+            text: var("TT_COLOR_POPUP_TEXT"),
+            snooze: var("TT_COLOR_POPUP_SNOOZE"),
+            restart: var("TT_COLOR_POPUP_RESTART"),
+            stop: var("TT_COLOR_POPUP_STOP"),
+        }
+    }
+}
+
+/// Resolved popup colors as RGB triples.
+#[derive(Clone, Copy)]
+pub struct PopupPalette {
+    pub message: [u8; 3],
+    pub text: [u8; 3],
+    pub muted: [u8; 3],
+    pub snooze: [u8; 3],
+    pub restart: [u8; 3],
+    pub stop: [u8; 3],
+    pub error: [u8; 3],
+}
+
+impl PopupPalette {
+    pub fn new(theme: Theme, overrides: &PopupColorOverrides) -> Self {
+        let base = match theme {
+            Theme::Dark => PopupPalette {
+                message: [144, 238, 144],
+                text: [255, 255, 255],
+                muted: [160, 160, 160],
+                snooze: [128, 128, 255],
+                restart: [0, 255, 128],
+                stop: [255, 0, 0],
+                error: [255, 100, 100],
+            },
+            Theme::Light => PopupPalette {
+                message: [20, 120, 40],
+                text: [30, 30, 30],
+                muted: [110, 110, 110],
+                snooze: [60, 60, 200],
+                restart: [0, 140, 70],
+                stop: [200, 0, 0],
+                error: [200, 40, 40],
+            },
+        };
+        let pick = |value: &Option<String>, default: [u8; 3]| {
+            value
+                .as_deref()
+                .and_then(|v| parse(v, theme))
+                .map(ColorSpec::to_rgb)
+                .unwrap_or(default)
+        };
+        PopupPalette {
+            message: pick(&overrides.message, base.message),
+            text: pick(&overrides.text, base.text),
+            muted: base.muted,
+            snooze: pick(&overrides.snooze, base.snooze),
+            restart: pick(&overrides.restart, base.restart),
+            stop: pick(&overrides.stop, base.stop),
+            error: base.error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_follow_the_theme() {
+        assert_eq!(parse("green", Theme::Dark), Some(ColorSpec::Ansi(151)));
+        assert_eq!(parse(" Green ", Theme::Light), Some(ColorSpec::Ansi(28)));
+        assert_eq!(parse("white", Theme::Light), Some(ColorSpec::Ansi(235)));
+    }
+
+    #[test]
+    fn codes_and_hex() {
+        assert_eq!(parse("114", Theme::Dark), Some(ColorSpec::Ansi(114)));
+        assert_eq!(parse("0", Theme::Light), Some(ColorSpec::Ansi(0)));
+        assert_eq!(parse("#7aa2f7", Theme::Dark), Some(ColorSpec::Rgb(0x7a, 0xa2, 0xf7)));
+        assert_eq!(parse("#7AA2F7", Theme::Light), Some(ColorSpec::Rgb(0x7a, 0xa2, 0xf7)));
+    }
+
+    #[test]
+    fn bad_values() {
+        assert_eq!(parse("256", Theme::Dark), None);
+        assert_eq!(parse("chartreuse", Theme::Dark), None);
+        assert_eq!(parse("", Theme::Dark), None);
+        assert_eq!(parse_hex("7aa2f7"), None);
+        assert_eq!(parse_hex("#7aa2f"), None);
+        assert_eq!(parse_hex("#7aa2fg"), None);
+        assert_eq!(parse_hex("#+1+2+3"), None);
+        // Six bytes, but not six hex digits.
+        assert_eq!(parse_hex("#aébcd"), None);
+    }
+}
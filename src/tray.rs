@@ -0,0 +1,218 @@
+//! `tt tray`: a system tray icon showing the nearest countdown.
+//!
+//! The menu lists the active timers (with pause/resume and kill), quick-start presets from
+//! TT_TRAY_PRESETS and a kill-all entry. Everything goes through the same SQLite database as the
+//! CLI, so timers started from the terminal show up in the tray and the other way around.
+//! The tray speaks the StatusNotifierItem protocol, so it is Linux only; macOS and Windows
+//! builds have no tray and point to `tt -a` and `tt status --watch` instead. It is built with
+//! the `tray` feature so headless builds don't pull in D-Bus.
+
+#[cfg(all(target_os = "linux", feature = "tray"))]
+pub fn run() {
+    use ksni::blocking::TrayMethods;
+    use std::time::Duration;
+
+    let tray = linux::TimerTray {
+        timers: Vec::new(),
+        presets: linux::presets(),
+    };
+    let handle = match tray.spawn() {
+        Ok(handle) => handle,
+        Err(e) => {
+            eprintln!("Could not create the tray icon: {}", e);
+            eprintln!("tt tray needs a desktop with a system tray (StatusNotifierItem), e.g. KDE, or GNOME with the AppIndicator extension.");
+            std::process::exit(1);
+        }
+    };
+    let conn = crate::init_db().unwrap_or_else(|e| {
+        eprintln!("Could not open the timer database: {}", e);
+        std::process::exit(1);
+    });
+    // Refresh the countdowns once a second until the tray goes away.
+    while !handle.is_closed() {
+        let timers = crate::load_active_timers_db(&conn)
+            .map(|timers| timers.into_iter().filter(|t| t.paused || !t.remaining.is_zero()).collect())
+            .unwrap_or_default();
+        handle.update(|tray| tray.timers = timers);
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[cfg(all(target_os = "linux", not(feature = "tray")))]
+pub fn run() {
+    eprintln!("This build of tt has no tray support. Rebuild with `cargo build --release --features tray`, or use `tt -a` to manage active timers.");
+    std::process::exit(1);
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn run() {
+    eprintln!("tt tray is only available on Linux. Use `tt -a` to manage active timers, or `tt status --watch` in a menu-bar tool for the countdown.");
+    std::process::exit(1);
+}
+
+#[cfg(all(target_os = "linux", feature = "tray"))]
+mod linux {
+    use crate::format_countdown;
+    use crate::{ActiveTimer, TimerState};
+    use ksni::menu::{StandardItem, SubMenu};
+    use ksni::{MenuItem, ToolTip};
+
+    /// Durations offered under "Start timer", from TT_TRAY_PRESETS (default "5m,15m,25m,1h").
+    pub fn presets() -> Vec<String> {
+        std::env::var("TT_TRAY_PRESETS")
+            .unwrap_or_else(|_| "5m,15m,25m,1h".to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|s| humantime::parse_duration(s).is_ok())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Starts a background timer by running this binary, exactly as `tt <duration>` would.
+    fn start_timer(duration: &str) {
+        use std::process::{Command, Stdio};
+
+        if let Ok(exe) = std::env::current_exe() {
+            let _ = Command::new(exe)
+                .arg(duration)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+
+    pub struct TimerTray {
+        pub timers: Vec<ActiveTimer>,
+        pub presets: Vec<String>,
+    }
+
+    impl TimerTray {
+        /// The running timer that goes off first, falling back to a paused one.
+        fn nearest(&self) -> Option<&ActiveTimer> {
+            let running = self.timers.iter().filter(|t| !t.paused).min_by_key(|t| t.remaining);
+            running.or_else(|| self.timers.first())
+        }
+
+        fn label(timer: &ActiveTimer) -> String {
+            let state = if timer.state() == TimerState::Paused { " ⏸" } else { "" };
+            let message = if timer.message.is_empty() { timer.duration.as_str() } else { timer.message.as_str() };
+            format!("#{} {} — {}{}", timer.id, message, format_countdown(timer.remaining), state)
+        }
+    }
+
+    /// Runs a database action from a menu item. The next refresh picks up the change.
+    fn with_db(action: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<bool>) {
+        if let Ok(conn) = crate::init_db() {
+            let _ = action(&conn);
+        }
+    }
+
+    impl ksni::Tray for TimerTray {
+        fn id(&self) -> String {
+            env!("CARGO_PKG_NAME").into()
+        }
+
+        fn title(&self) -> String {
+            match self.nearest() {
+                Some(timer) => format!("⏱ {}", format_countdown(timer.remaining)),
+                None => "Terminal Timer".into(),
+            }
+        }
+
+        fn icon_name(&self) -> String {
+            "alarm-symbolic".into()
+        }
+
+        fn tool_tip(&self) -> ToolTip {
+            let description = if self.timers.is_empty() {
+                "No active timers".to_string()
+            } else {
+                self.timers.iter().map(Self::label).collect::<Vec<_>>().join("\n")
+            };
+            ToolTip {
+                title: self.title(),
+                description,
+                ..Default::default()
+            }
+        }
+
+        fn menu(&self) -> Vec<MenuItem<Self>> {
+            let mut items: Vec<MenuItem<Self>> = Vec::new();
+            if self.timers.is_empty() {
+                items.push(StandardItem { label: "No active timers".into(), enabled: false, ..Default::default() }.into());
+            }
+            for timer in &self.timers {
+                let id = timer.id;
+                let toggle: MenuItem<Self> = if timer.paused {
+                    StandardItem {
+                        label: "Resume".into(),
+                        icon_name: "media-playback-start".into(),
+                        activate: Box::new(move |_: &mut Self| with_db(|conn| crate::resume_active_timer_db(conn, id))),
+                        ..Default::default()
+                    }
+                    .into()
+                } else {
+                    StandardItem {
+                        label: "Pause".into(),
+                        icon_name: "media-playback-pause".into(),
+                        activate: Box::new(move |_: &mut Self| with_db(|conn| crate::pause_active_timer_db(conn, id))),
+                        ..Default::default()
+                    }
+                    .into()
+                };
+                let kill = StandardItem {
+                    label: "Kill".into(),
+                    icon_name: "process-stop".into(),
+                    activate: Box::new(move |_: &mut Self| with_db(|conn| crate::kill_active_timer_db(conn, id))),
+                    ..Default::default()
+                }
+                .into();
+                items.push(SubMenu { label: Self::label(timer), submenu: vec![toggle, kill], ..Default::default() }.into());
+            }
+
+            items.push(MenuItem::Separator);
+            let presets = self
+                .presets
+                .iter()
+                .map(|preset| {
+                    let duration = preset.clone();
+                    StandardItem {
+                        label: preset.clone(),
+                        activate: Box::new(move |_: &mut Self| start_timer(&duration)),
+                        ..Default::default()
+                    }
+                    .into()
+                })
+                .collect();
+            items.push(SubMenu { label: "Start timer".into(), submenu: presets, ..Default::default() }.into());
+            items.push(
+                StandardItem {
+                    label: "Kill all".into(),
+                    enabled: !self.timers.is_empty(),
+                    activate: Box::new(|_: &mut Self| {
+                        with_db(|conn| {
+                            for timer in crate::load_active_timers_db(conn)? {
+                                crate::kill_active_timer_db(conn, timer.id)?;
+                            }
+                            Ok(true)
+                        })
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+            );
+            items.push(MenuItem::Separator);
+            items.push(
+                StandardItem {
+                    label: "Quit tray".into(),
+                    icon_name: "application-exit".into(),
+                    activate: Box::new(|_: &mut Self| std::process::exit(0)),
+                    ..Default::default()
+                }
+                .into(),
+            );
+            items
+        }
+    }
+}